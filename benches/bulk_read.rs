@@ -0,0 +1,50 @@
+// compares reading a 1 MB blob with the parser, which reads payloads in bulk, against pushing it
+// into a `Vec` one byte at a time like the parser used to. run with `cargo bench`
+
+use bsdf::{Encoder, Item, Parser};
+use std::hint::black_box;
+use std::io::{Cursor, Read};
+use std::time::{Duration, Instant};
+
+const BLOB_SIZE: usize = 1 << 20;
+const ROUNDS: u32 = 20;
+
+fn time(name: &str, mut f: impl FnMut()) {
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+    }
+    println!("{name:<12} {best:?}");
+}
+
+fn main() {
+    let data: Vec<u8> = (0..BLOB_SIZE).map(|n| n as u8).collect();
+    let mut document = Vec::new();
+    Encoder::new(&mut document)
+        .write(&Item::Blob(data))
+        .unwrap();
+
+    // the cursor is not a slice, so the parser can't borrow and has to go through `Read`
+    time("bulk", || {
+        let item = Parser::new(Cursor::new(&document)).parse().unwrap();
+        black_box(item);
+    });
+
+    time("byte push", || {
+        let mut reader = Cursor::new(&document);
+        // header, type byte, three large sizes, compression, checksum and the alignment
+        let mut header = [0; 6 + 1 + 3 * 9 + 3];
+        reader.read_exact(&mut header).unwrap();
+        let padding = usize::from(header[header.len() - 1]);
+        reader.read_exact(&mut vec![0; padding]).unwrap();
+
+        let mut blob = Vec::new();
+        for byte in reader.bytes().take(BLOB_SIZE) {
+            blob.push(byte.unwrap());
+        }
+        assert_eq!(blob.len(), BLOB_SIZE);
+        black_box(blob);
+    });
+}
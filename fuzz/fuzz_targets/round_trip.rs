@@ -0,0 +1,15 @@
+// cargo fuzz run round_trip
+// every generated item has to come back unchanged after writing and parsing it
+
+#![no_main]
+
+use bsdf::{HashableItem, Item};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|item: Item| {
+    let data = bsdf::to_vec(&item).expect("any item can be written");
+    let parsed = bsdf::from_slice(&data).expect("written data can be parsed");
+
+    // floats are compared by their bits, so NaN round trips too
+    assert!(parsed.map(HashableItem) == Some(HashableItem(item)));
+});
@@ -0,0 +1,23 @@
+#![no_std]
+
+extern crate alloc;
+
+#[cfg(test)]
+use alloc::string::String;
+#[cfg(test)]
+use bsdf::{Item, Map, Parser};
+
+#[test]
+fn parses_normal_map() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+
+    let expected = Item::Map(Map::from_iter([
+        (String::from("test"), Item::Int16(1)),
+        (String::from("test1"), Item::Int16(2)),
+        (String::from("test3"), Item::Int16(4)),
+    ]));
+
+    let mut parser = Parser::new(data.as_slice());
+    assert_eq!(parser.parse().unwrap(), Some(expected));
+}
@@ -0,0 +1,230 @@
+use crate::consts;
+use crate::{Compression, Error};
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+/// blob framing as it was read from the stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobMeta {
+    pub allocated_size: usize,
+    pub used_size: usize,
+    pub data_size: usize,
+    /// one of the `consts::COMPRESSION_*` values
+    pub compression: u8,
+    /// one of the `consts::CHECKSUM_*` values
+    pub checksum_setting: u8,
+    /// hash of the stored data, if the blob has one
+    pub checksum: Option<Vec<u8>>,
+    /// padding bytes between the header and the data
+    pub alignment: u8,
+}
+
+/// blob data together with the compression it is written with, see `Item::CompressedBlob`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Blob {
+    /// the uncompressed data
+    pub data: Vec<u8>,
+    pub compression: Compression,
+    /// allocated bytes after the stored data, written as zeros so the blob can grow in place
+    pub spare: usize,
+}
+
+impl Blob {
+    pub fn new(data: Vec<u8>, compression: Compression) -> Blob {
+        Blob {
+            data,
+            compression,
+            spare: 0,
+        }
+    }
+
+    /// the same blob with `spare` allocated bytes after its stored data
+    pub fn with_spare(mut self, spare: usize) -> Blob {
+        self.spare = spare;
+        self
+    }
+}
+
+/// the number of hash bytes that follow a checksum setting, `None` if no hash follows
+pub(crate) fn checksum_len(setting: u8) -> Option<usize> {
+    match setting {
+        consts::CHECKSUM_SET => Some(16),
+        consts::CHECKSUM_CRC32 => Some(4),
+        _ => None,
+    }
+}
+
+/// the hash of the stored data, computed while it is read so the data isn't scanned twice
+pub(crate) enum Checksum {
+    /// the blob has no hash or it is not verified
+    Unchecked,
+    /// the hash can't be verified without this feature, reported by `finish`
+    #[cfg_attr(all(feature = "md5", feature = "crc32"), allow(dead_code))]
+    NotCompiled(crate::InvalidExtension),
+    #[cfg(feature = "md5")]
+    Md5(md5::Context, Vec<u8>),
+    #[cfg(feature = "crc32")]
+    Crc32(crc32fast::Hasher, Vec<u8>),
+}
+
+impl Checksum {
+    pub fn new(meta: &BlobMeta, verify_checksums: bool) -> Checksum {
+        match (&meta.checksum, verify_checksums) {
+            (Some(hash), true) => match meta.checksum_setting {
+                consts::CHECKSUM_CRC32 => crc32_checksum(hash),
+                _ => md5_checksum(hash),
+            },
+            _ => Checksum::Unchecked,
+        }
+    }
+
+    #[cfg_attr(not(any(feature = "md5", feature = "crc32")), allow(unused_variables))]
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Checksum::Unchecked | Checksum::NotCompiled(_) => {}
+            #[cfg(feature = "md5")]
+            Checksum::Md5(context, _) => context.consume(data),
+            #[cfg(feature = "crc32")]
+            Checksum::Crc32(hasher, _) => hasher.update(data),
+        }
+    }
+
+    /// compares the hash of everything passed to `update` with the stored one
+    pub fn finish(self) -> Result<(), Error> {
+        let valid = match self {
+            Checksum::Unchecked => true,
+            Checksum::NotCompiled(extension) => return Err(Error::from(extension)),
+            #[cfg(feature = "md5")]
+            Checksum::Md5(context, hash) => context.compute().as_slice() == hash,
+            #[cfg(feature = "crc32")]
+            Checksum::Crc32(hasher, hash) => hasher.finalize().to_le_bytes() == hash.as_slice(),
+        };
+        match valid {
+            true => Ok(()),
+            false => Err(Error::InvalidBlobHash),
+        }
+    }
+}
+
+#[cfg(feature = "md5")]
+fn md5_checksum(hash: &[u8]) -> Checksum {
+    Checksum::Md5(md5::Context::new(), hash.to_vec())
+}
+
+#[cfg(not(feature = "md5"))]
+fn md5_checksum(_: &[u8]) -> Checksum {
+    Checksum::NotCompiled(crate::InvalidExtension::Md5NotCompiled)
+}
+
+#[cfg(feature = "crc32")]
+fn crc32_checksum(hash: &[u8]) -> Checksum {
+    Checksum::Crc32(crc32fast::Hasher::new(), hash.to_vec())
+}
+
+#[cfg(not(feature = "crc32"))]
+fn crc32_checksum(_: &[u8]) -> Checksum {
+    Checksum::NotCompiled(crate::InvalidExtension::Crc32NotCompiled)
+}
+
+/// verifies and decompresses the stored blob data
+pub(crate) fn unpack<'b>(
+    meta: &BlobMeta,
+    data: Cow<'b, [u8]>,
+    verify_checksums: bool,
+) -> Result<Cow<'b, [u8]>, Error> {
+    let mut checksum = Checksum::new(meta, verify_checksums);
+    checksum.update(&data);
+    checksum.finish()?;
+    decompress(meta, data)
+}
+
+/// decompresses data that is already verified
+pub(crate) fn decompress<'b>(meta: &BlobMeta, data: Cow<'b, [u8]>) -> Result<Cow<'b, [u8]>, Error> {
+    let data = match Compression::try_from(meta.compression)? {
+        Compression::None => data,
+        Compression::Zlib => Cow::Owned(decompress_zlib(&data, meta.data_size)?),
+        Compression::Bz2 => Cow::Owned(decompress_bz2(&data, meta.data_size)?),
+        Compression::Lz4 => Cow::Owned(decompress_lz4(&data, meta.data_size)?),
+    };
+
+    Ok(data)
+}
+
+/// reads at most one byte more than the declared `size`, the limits of the parser are charged
+/// with that size so the data can't expand past it. a size that doesn't match is an error, and so
+/// is data the decompressor can't read. that is `Error::InvalidBlob` and not `Error::Eof`, the
+/// document around it is complete
+#[cfg(any(feature = "zlib", feature = "bz2", feature = "lz4"))]
+fn read_decompressed(decompressor: impl std::io::Read, size: usize) -> Result<Vec<u8>, Error> {
+    use crate::parser::MAX_PREALLOCATE;
+    use std::io::Read;
+
+    let mut buffer = Vec::with_capacity(size.min(MAX_PREALLOCATE));
+    decompressor
+        .take(size as u64 + 1)
+        .read_to_end(&mut buffer)
+        .map_err(|_| Error::InvalidBlob)?;
+    match buffer.len().cmp(&size) {
+        core::cmp::Ordering::Greater => Err(Error::LimitExceeded),
+        core::cmp::Ordering::Less => Err(Error::InvalidBlob),
+        core::cmp::Ordering::Equal => Ok(buffer),
+    }
+}
+
+#[cfg(feature = "zlib")]
+fn decompress_zlib(data: &[u8], size: usize) -> Result<Vec<u8>, Error> {
+    read_decompressed(flate2::read::ZlibDecoder::new(data), size)
+}
+
+#[cfg(not(feature = "zlib"))]
+fn decompress_zlib(_: &[u8], _: usize) -> Result<Vec<u8>, Error> {
+    Err(Error::from(crate::InvalidExtension::ZlibNotCompiled))
+}
+
+#[cfg(feature = "bz2")]
+fn decompress_bz2(data: &[u8], size: usize) -> Result<Vec<u8>, Error> {
+    read_decompressed(bzip2::read::BzDecoder::new(data), size)
+}
+
+#[cfg(not(feature = "bz2"))]
+fn decompress_bz2(_: &[u8], _: usize) -> Result<Vec<u8>, Error> {
+    Err(Error::from(crate::InvalidExtension::Bz2NotCompiled))
+}
+
+#[cfg(feature = "lz4")]
+fn decompress_lz4(data: &[u8], size: usize) -> Result<Vec<u8>, Error> {
+    read_decompressed(lz4_flex::frame::FrameDecoder::new(data), size)
+}
+
+#[cfg(not(feature = "lz4"))]
+fn decompress_lz4(_: &[u8], _: usize) -> Result<Vec<u8>, Error> {
+    Err(Error::from(crate::InvalidExtension::Lz4NotCompiled))
+}
+
+/// a document with `data` as a zlib blob at the root, declared as `data_size` bytes. the last
+/// `cut` bytes of the deflate stream are dropped, the blob around it stays complete
+#[cfg(all(test, feature = "zlib"))]
+pub(crate) fn zlib_blob_document(data: &[u8], data_size: usize, cut: usize) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut compressor =
+        flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    compressor.write_all(data).unwrap();
+    let mut compressed = compressor.finish().unwrap();
+    compressed.truncate(compressed.len() - cut);
+
+    let meta = BlobMeta {
+        allocated_size: compressed.len(),
+        used_size: compressed.len(),
+        data_size,
+        compression: consts::COMPRESSION_ZLIB,
+        checksum_setting: consts::CHECKSUM_NOT_SET,
+        checksum: None,
+        alignment: 0,
+    };
+    let mut buffer = Vec::new();
+    crate::Encoder::new(&mut buffer)
+        .write_blob_raw(&meta, &compressed)
+        .unwrap();
+    buffer
+}
@@ -0,0 +1,68 @@
+use crate::consts;
+use crate::{Error, InvalidExtension};
+
+/// how blob data is stored, the `consts::COMPRESSION_*` settings as an enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Zlib,
+    Bz2,
+    /// not part of the spec, other implementations can't read it
+    Lz4,
+}
+
+impl Compression {
+    /// the `consts::COMPRESSION_*` value written in the blob header
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Compression::None => consts::COMPRESSION_NOT_SET,
+            Compression::Zlib => consts::COMPRESSION_ZLIB,
+            Compression::Bz2 => consts::COMPRESSION_BZ2,
+            Compression::Lz4 => consts::COMPRESSION_LZ4,
+        }
+    }
+}
+
+impl From<Compression> for u8 {
+    fn from(compression: Compression) -> u8 {
+        compression.to_u8()
+    }
+}
+
+/// unknown settings fail with `InvalidExtension::InvalidCompressionSetting`
+impl TryFrom<u8> for Compression {
+    type Error = Error;
+
+    fn try_from(setting: u8) -> Result<Compression, Error> {
+        match setting {
+            consts::COMPRESSION_NOT_SET => Ok(Compression::None),
+            consts::COMPRESSION_ZLIB => Ok(Compression::Zlib),
+            consts::COMPRESSION_BZ2 => Ok(Compression::Bz2),
+            consts::COMPRESSION_LZ4 => Ok(Compression::Lz4),
+            setting => Err(Error::from(InvalidExtension::InvalidCompressionSetting(
+                setting,
+            ))),
+        }
+    }
+}
+
+#[test]
+fn compression_conversion_test() {
+    let settings = [
+        (0, Compression::None),
+        (1, Compression::Zlib),
+        (2, Compression::Bz2),
+        (3, Compression::Lz4),
+    ];
+    for (byte, compression) in settings {
+        assert_eq!(Compression::try_from(byte), Ok(compression));
+        assert_eq!(compression.to_u8(), byte);
+        assert_eq!(u8::from(compression), byte);
+    }
+
+    assert_eq!(
+        Compression::try_from(4),
+        Err(Error::from(InvalidExtension::InvalidCompressionSetting(4)))
+    );
+}
@@ -1,10 +1,53 @@
 pub const PREFIX: [u8; 4] = *b"BSDF";
 
+pub const VERSION_MAJOR: u8 = 2;
+pub const VERSION_MINOR: u8 = 2;
+pub const MAX_SUPPORTED_MAJOR: u8 = 2;
+
+/// the spec checksum, followed by the 16 byte md5 hash of the stored data
 pub const CHECKSUM_SET: u8 = 0xFF;
+pub const CHECKSUM_NOT_SET: u8 = 0x00;
+/// not part of the BSDF spec, followed by the crc32 of the stored data as a little endian u32
+pub const CHECKSUM_CRC32: u8 = 0x01;
 
+/// followed by the size as a u64
 pub const LARGE_SIZE: u8 = 253;
+/// sizes below this are a single byte, larger ones use `LARGE_SIZE`. 251 and 252 are reserved
+/// and not valid size bytes, 254 and 255 are only valid for lists and maps as `STREAM_*`
 pub const SMALL_SIZE_CUTOFF: u8 = 251;
+/// the bytes `size` is written with, a single byte or `LARGE_SIZE` and a u64
+pub(crate) fn encode_size(size: usize, buffer: &mut [u8; 9]) -> &[u8] {
+    if size < SMALL_SIZE_CUTOFF as usize {
+        buffer[0] = size as u8;
+        &buffer[..1]
+    } else {
+        buffer[0] = LARGE_SIZE;
+        buffer[1..].copy_from_slice(&(size as u64).to_le_bytes());
+        &buffer[..]
+    }
+}
+
+/// the size in the first byte of a size, `None` when a u64 with the size follows
+pub(crate) fn decode_small_size(first_byte: u8) -> Result<Option<usize>, crate::Error> {
+    match first_byte {
+        LARGE_SIZE => Ok(None),
+        SMALL_SIZE_CUTOFF..=u8::MAX => Err(crate::Error::InvalidSize),
+        size => Ok(Some(size as usize)),
+    }
+}
+
+// streamed collections, both followed by a u64. closed streams know their length,
+// open streams run until the end of the data
+pub const STREAM_CLOSED: u8 = 254;
+pub const STREAM_OPEN: u8 = 255;
+
+/// not part of the BSDF spec, which has no half precision floats and marks extensions by an
+/// uppercase type byte instead. followed by a little endian f16, other implementations can't read
+/// documents with it. only read with the `half` feature, never written
+pub const TYPE_F16: u8 = b'e';
 
 pub const COMPRESSION_NOT_SET: u8 = 0;
 pub const COMPRESSION_ZLIB: u8 = 1;
 pub const COMPRESSION_BZ2: u8 = 2;
+/// not part of the BSDF spec, which only defines 0, 1 and 2. the data is an lz4 frame
+pub const COMPRESSION_LZ4: u8 = 3;
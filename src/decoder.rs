@@ -0,0 +1,160 @@
+// buffers input that arrives in pieces and hands out a document once it is complete. every attempt
+// parses the buffer from the start, nothing of a partial parse is kept besides the bytes
+
+use crate::visitor::TreeBuilder;
+use crate::{Error, Item, Limits, Parser};
+use alloc::vec::Vec;
+
+/// incremental parsing for non-blocking io: `feed` the bytes as they arrive and take the documents
+/// out with `next_item`.
+///
+/// an open stream has no end marker, so the decoder can't tell where a document with one ends.
+/// those fail with `Error::OpenStream`.
+///
+/// every attempt parses the buffered document from its start. a document of `n` bytes that
+/// arrives in `k` pieces costs `n * k` if `next_item` is called after every piece, so feed larger
+/// pieces for large documents. a call without new bytes since the last one is free
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+    limits: Limits,
+    // the buffer length of the last attempt that needed more bytes
+    incomplete: Option<usize>,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    pub fn with_limits(limits: Limits) -> Decoder {
+        Decoder {
+            buffer: Vec::new(),
+            limits,
+            incomplete: None,
+        }
+    }
+
+    /// adds bytes to the end of the buffer
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// the next document once all of its bytes are buffered, `Ok(None)` while more bytes are
+    /// needed. the bytes of a returned document are removed from the buffer, after an error the
+    /// buffer is left as it was
+    pub fn next_item(&mut self) -> Result<Option<Item>, Error> {
+        if self.incomplete == Some(self.buffer.len()) {
+            return Ok(None);
+        }
+
+        let mut parser = Parser::with_limits(self.buffer.as_slice(), self.limits);
+        let mut builder = TreeBuilder::new(false).reject_open_streams();
+        match parser.parse_with(&mut builder) {
+            Err(e) if e.is_incomplete() => {
+                self.incomplete = Some(self.buffer.len());
+                return Ok(None);
+            }
+            result => result?,
+        };
+        let item = match builder.finish() {
+            Some(item) => item,
+            // a header without a value yet
+            None => {
+                self.incomplete = Some(self.buffer.len());
+                return Ok(None);
+            }
+        };
+        let consumed = parser.bytes_consumed();
+        self.buffer.drain(..consumed);
+        self.incomplete = None;
+        Ok(Some(item))
+    }
+
+    /// the number of buffered bytes that are not part of a returned document yet
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+#[test]
+fn decoder_split_feed_test() {
+    use alloc::string::String;
+
+    // copied from python
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+
+    let mut decoder = Decoder::new();
+    decoder.feed(&data[..4]);
+    assert_eq!(decoder.next_item(), Ok(None));
+    decoder.feed(&data[4..30]);
+    assert_eq!(decoder.next_item(), Ok(None));
+    decoder.feed(&data[30..]);
+
+    let item = decoder.next_item().unwrap().unwrap();
+    assert_eq!(item["nested"]["list"][2], Item::Int64(123456789));
+    assert_eq!(
+        item["nested"]["data"],
+        Item::String(String::from("some text"))
+    );
+    assert_eq!(decoder.buffered(), 0);
+    assert_eq!(decoder.next_item(), Ok(None));
+}
+
+#[test]
+fn decoder_two_documents_test() {
+    let mut decoder = Decoder::new();
+    decoder.feed(b"BSDF\x02\x02h\x01\x00BSDF\x02\x02h\x02");
+    assert_eq!(decoder.next_item(), Ok(Some(Item::Int16(1))));
+    assert_eq!(decoder.next_item(), Ok(None));
+    decoder.feed(b"\x00");
+    assert_eq!(decoder.next_item(), Ok(Some(Item::Int16(2))));
+}
+
+#[test]
+fn decoder_open_stream_test() {
+    let mut decoder = Decoder::new();
+    decoder.feed(b"BSDF\x02\x02l\xff\x00\x00");
+    assert_eq!(decoder.next_item(), Ok(None));
+    decoder.feed(b"\x00\x00\x00\x00\x00\x00h\x01\x00");
+    assert_eq!(
+        decoder.next_item().map_err(Error::into_inner),
+        Err(Error::OpenStream)
+    );
+
+    // a closed stream has its length
+    let mut decoder = Decoder::new();
+    decoder.feed(b"BSDF\x02\x02l\xfe\x01\x00\x00\x00\x00\x00\x00\x00h\x01\x00");
+    assert_eq!(
+        decoder.next_item(),
+        Ok(Some(Item::List(alloc::vec![Item::Int16(1)])))
+    );
+}
+
+#[test]
+fn decoder_corrupt_test() {
+    let mut decoder = Decoder::new();
+    decoder.feed(b"BSDF\x02\x02x");
+    assert_eq!(
+        decoder.next_item().map_err(Error::into_inner),
+        Err(Error::InvalidType(b'x'))
+    );
+}
+
+#[cfg(all(test, feature = "zlib"))]
+mod zlib_test {
+    use super::*;
+
+    #[test]
+    fn decoder_corrupt_zlib_test() {
+        // the deflate stream is cut off, the blob around it is complete
+        let data = crate::blob::zlib_blob_document(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 0], 10, 6);
+
+        let mut decoder = Decoder::new();
+        decoder.feed(&data);
+        assert_eq!(
+            decoder.next_item().map_err(Error::into_inner),
+            Err(Error::InvalidBlob)
+        );
+    }
+}
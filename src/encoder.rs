@@ -0,0 +1,1070 @@
+// everything is little endian
+
+use crate::blob;
+use crate::consts;
+use crate::{BlobMeta, Compression, Error, InvalidExtension, Item, Map};
+use std::io::Write;
+
+#[cfg(feature = "with-serde")]
+mod serde_impl;
+
+/// how `Item::Int16` and `Item::Int64` are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntEncoding {
+    /// keep the variant of the item
+    #[default]
+    Exact,
+    /// use the smallest integer type the value fits in, the value survives a round trip
+    /// but the variant may not
+    Compact,
+}
+
+/// how `Item::F64` is written, this also applies to `f64` values serialized with serde
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatEncoding {
+    /// keep the variant of the item, the bits survive a round trip
+    #[default]
+    Exact,
+    /// write values that an `f32` holds without loss as `F32`, so `1.0` narrows but `3.14`
+    /// does not. the value survives a round trip but the variant may not
+    Compact,
+}
+
+pub struct Encoder<W> {
+    writer: W,
+    // bytes written so far, needed for the blob byte alignment
+    position: usize,
+    compression: Compression,
+    checksum: u8,
+    int_encoding: IntEncoding,
+    float_encoding: FloatEncoding,
+    version: (u8, u8),
+    canonical: bool,
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(writer: W) -> Encoder<W> {
+        Encoder {
+            writer,
+            position: 0,
+            compression: Compression::None,
+            checksum: consts::CHECKSUM_NOT_SET,
+            int_encoding: IntEncoding::default(),
+            float_encoding: FloatEncoding::default(),
+            version: (consts::VERSION_MAJOR, consts::VERSION_MINOR),
+            canonical: false,
+        }
+    }
+
+    /// the version written in the header, defaults to 2.2 like the reference implementation.
+    /// only the header changes, the data is written the same way
+    pub fn version(mut self, major: u8, minor: u8) -> Self {
+        self.version = (major, minor);
+        self
+    }
+
+    pub fn int_encoding(mut self, int_encoding: IntEncoding) -> Self {
+        self.int_encoding = int_encoding;
+        self
+    }
+
+    pub fn float_encoding(mut self, float_encoding: FloatEncoding) -> Self {
+        self.float_encoding = float_encoding;
+        self
+    }
+
+    /// write the entries of every map sorted by key, so equal items give the same bytes whatever
+    /// order their maps iterate in. values serialized with serde are written in the order serde
+    /// hands them over
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// compression for blobs, `Compression::None` by default
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// checksum setting for blobs, one of the `consts::CHECKSUM_*` values. no checksum is written
+    /// by default, `consts::CHECKSUM_SET` is the md5 hash from the spec
+    pub fn checksum(mut self, checksum: u8) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// whether blobs get the md5 hash, `checksum` with `consts::CHECKSUM_SET` or
+    /// `consts::CHECKSUM_NOT_SET`. off by default like the reference writer
+    pub fn blob_checksum(self, enabled: bool) -> Self {
+        match enabled {
+            true => self.checksum(consts::CHECKSUM_SET),
+            false => self.checksum(consts::CHECKSUM_NOT_SET),
+        }
+    }
+
+    pub fn write(&mut self, item: &Item) -> Result<(), Error> {
+        self.write_header()?;
+        self.write_item(item)?;
+        self.writer.flush().map_err(Error::Writer)
+    }
+
+    /// writes a document with a blob at the root, the counterpart of `Parser::parse_blob_raw`.
+    /// `data` is stored as is, so it has to be compressed as `meta.compression` says already.
+    /// the `allocated_size - used_size` spare bytes are written as zeros so the blob can grow in
+    /// place later. `meta.alignment` is not used, the alignment is recomputed. blobs inside a
+    /// document keep their spare bytes through `Blob::spare`
+    pub fn write_blob_raw(&mut self, meta: &BlobMeta, data: &[u8]) -> Result<(), Error> {
+        if meta.used_size != data.len() {
+            return Err(Error::LengthMismatch {
+                declared: meta.used_size,
+                written: data.len(),
+            });
+        }
+        if meta.allocated_size < meta.used_size
+            || meta.checksum.as_ref().map(Vec::len) != blob::checksum_len(meta.checksum_setting)
+        {
+            return Err(Error::InvalidBlob);
+        }
+
+        self.write_header()?;
+        self.write_blob_parts(
+            meta.allocated_size,
+            meta.data_size,
+            meta.compression,
+            meta.checksum_setting,
+            meta.checksum.as_deref(),
+            data,
+        )?;
+        self.writer.flush().map_err(Error::Writer)
+    }
+
+    /// writes the header and the start of a root list, the elements are then written one at a
+    /// time through the returned writer. without a length the list is written as an open stream
+    pub fn list_writer(&mut self, len: Option<usize>) -> Result<ListWriter<'_, W>, Error> {
+        self.write_header()?;
+        self.write_bytes(b"l")?;
+        match len {
+            Some(len) => self.write_size(len)?,
+            None => {
+                // the length of an open stream is never filled in, readers read until the end
+                self.write_bytes(&[consts::STREAM_OPEN])?;
+                self.write_bytes(&0u64.to_le_bytes())?;
+            }
+        }
+
+        Ok(ListWriter {
+            encoder: self,
+            declared: len,
+            written: 0,
+        })
+    }
+
+    fn write_header(&mut self) -> Result<(), Error> {
+        self.write_bytes(&consts::PREFIX)?;
+        self.write_bytes(&[self.version.0, self.version.1])
+    }
+
+    fn write_item(&mut self, item: &Item) -> Result<(), Error> {
+        match item {
+            Item::Void => self.write_bytes(b"v"),
+            Item::Bool(false) => self.write_bytes(b"n"),
+            Item::Bool(true) => self.write_bytes(b"y"),
+            Item::Int16(n) => self.write_int16(*n),
+            Item::Int64(n) => match (self.int_encoding, i16::try_from(*n)) {
+                (IntEncoding::Compact, Ok(n)) => self.write_int16(n),
+                _ => {
+                    self.write_bytes(b"i")?;
+                    self.write_bytes(&n.to_le_bytes())
+                }
+            },
+            Item::F32(v) => self.write_f32(*v),
+            Item::F64(v) => match self.float_encoding {
+                // comparing the bits keeps -0.0 and the NaN payloads as they are
+                FloatEncoding::Compact if f64::from(*v as f32).to_bits() == v.to_bits() => {
+                    self.write_f32(*v as f32)
+                }
+                _ => {
+                    self.write_bytes(b"d")?;
+                    self.write_bytes(&v.to_le_bytes())
+                }
+            },
+            Item::String(text) => {
+                self.write_bytes(b"s")?;
+                self.write_string(text)
+            }
+            Item::List(list) => self.write_list(list),
+            Item::Map(map) => self.write_map(map, item.extension_name()),
+            Item::Blob(data) => self.write_blob(data),
+            Item::CompressedBlob(blob) => {
+                self.write_blob_with(&blob.data, blob.compression, blob.spare)
+            }
+        }
+    }
+
+    fn write_int16(&mut self, n: i16) -> Result<(), Error> {
+        self.write_bytes(b"h")?;
+        self.write_bytes(&n.to_le_bytes())
+    }
+
+    fn write_f32(&mut self, v: f32) -> Result<(), Error> {
+        self.write_bytes(b"f")?;
+        self.write_bytes(&v.to_le_bytes())
+    }
+
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.writer.write_all(bytes).map_err(Error::Writer)?;
+        self.position += bytes.len();
+        Ok(())
+    }
+
+    /// counterpart of `Parser::parse_size`, sizes below the cutoff fit in a single byte
+    fn write_size(&mut self, size: usize) -> Result<(), Error> {
+        self.write_bytes(consts::encode_size(size, &mut [0; 9]))
+    }
+
+    fn write_string(&mut self, text: &str) -> Result<(), Error> {
+        self.write_size(text.len())?;
+        self.write_bytes(text.as_bytes())
+    }
+
+    fn write_list(&mut self, list: &[Item]) -> Result<(), Error> {
+        self.write_bytes(b"l")?;
+        self.write_size(list.len())?;
+
+        for item in list {
+            self.write_item(item)?;
+        }
+
+        Ok(())
+    }
+
+    /// with an extension name the type byte is uppercase and followed by the name
+    fn write_map(&mut self, map: &Map, extension: Option<&str>) -> Result<(), Error> {
+        match extension {
+            Some(name) => {
+                self.write_bytes(b"M")?;
+                self.write_string(name)?;
+            }
+            None => self.write_bytes(b"m")?,
+        }
+        self.write_size(map.len())?;
+
+        if self.canonical {
+            let mut entries: Vec<(&String, &Item)> = map.iter().collect();
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            for (key, item) in entries {
+                self.write_string(key)?;
+                self.write_item(item)?;
+            }
+        } else {
+            for (key, item) in map {
+                self.write_string(key)?;
+                self.write_item(item)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_blob(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.write_blob_with(data, self.compression, 0)
+    }
+
+    fn write_blob_with(
+        &mut self,
+        data: &[u8],
+        compression: Compression,
+        spare: usize,
+    ) -> Result<(), Error> {
+        let compressed = match compression {
+            Compression::None => None,
+            Compression::Zlib => Some(Self::compress_zlib(data)?),
+            Compression::Bz2 => Some(Self::compress_bz2(data)?),
+            Compression::Lz4 => Some(Self::compress_lz4(data)?),
+        };
+        let stored = compressed.as_deref().unwrap_or(data);
+        let checksum = match self.checksum {
+            consts::CHECKSUM_NOT_SET => None,
+            consts::CHECKSUM_SET => Some(Self::hash_md5(stored)?),
+            consts::CHECKSUM_CRC32 => Some(Self::hash_crc32(stored)?),
+            setting => {
+                return Err(Error::from(InvalidExtension::InvalidChecksumSetting(
+                    setting,
+                )))
+            }
+        };
+
+        self.write_blob_parts(
+            stored.len() + spare,
+            data.len(),
+            compression.to_u8(),
+            self.checksum,
+            checksum.as_deref(),
+            stored,
+        )
+    }
+
+    fn write_blob_parts(
+        &mut self,
+        allocated_size: usize,
+        data_size: usize,
+        compression: u8,
+        checksum_setting: u8,
+        checksum: Option<&[u8]>,
+        stored: &[u8],
+    ) -> Result<(), Error> {
+        self.write_bytes(b"b")?;
+        self.write_size(allocated_size)?;
+        self.write_size(stored.len())?;
+        self.write_size(data_size)?;
+        self.write_bytes(&[compression])?;
+        match checksum {
+            Some(hash) => {
+                self.write_bytes(&[checksum_setting])?;
+                self.write_bytes(hash)?;
+            }
+            None => self.write_bytes(&[consts::CHECKSUM_NOT_SET])?,
+        }
+
+        // uncompressed data starts at a multiple of 8, counted from after the alignment byte
+        let padding = match compression {
+            consts::COMPRESSION_NOT_SET => (8 - (self.position + 1) % 8) % 8,
+            _ => 0,
+        };
+        self.write_bytes(&[padding as u8])?;
+        self.write_bytes(&[0; 8][..padding])?;
+
+        self.write_bytes(stored)?;
+        self.write_bytes(&vec![0; allocated_size - stored.len()])
+    }
+
+    #[cfg(feature = "md5")]
+    fn hash_md5(data: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(md5::compute(data).to_vec())
+    }
+
+    #[cfg(not(feature = "md5"))]
+    fn hash_md5(_: &[u8]) -> Result<Vec<u8>, Error> {
+        Err(Error::from(InvalidExtension::Md5NotCompiled))
+    }
+
+    #[cfg(feature = "crc32")]
+    fn hash_crc32(data: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(crc32fast::hash(data).to_le_bytes().to_vec())
+    }
+
+    #[cfg(not(feature = "crc32"))]
+    fn hash_crc32(_: &[u8]) -> Result<Vec<u8>, Error> {
+        Err(Error::from(InvalidExtension::Crc32NotCompiled))
+    }
+
+    #[cfg(feature = "zlib")]
+    fn compress_zlib(data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut compressor =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+        compressor.write_all(data).map_err(Error::Writer)?;
+        compressor.finish().map_err(Error::Writer)
+    }
+
+    #[cfg(not(feature = "zlib"))]
+    fn compress_zlib(_: &[u8]) -> Result<Vec<u8>, Error> {
+        Err(Error::from(InvalidExtension::ZlibNotCompiled))
+    }
+
+    #[cfg(feature = "bz2")]
+    fn compress_bz2(data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut compressor = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::best());
+        compressor.write_all(data).map_err(Error::Writer)?;
+        compressor.finish().map_err(Error::Writer)
+    }
+
+    #[cfg(not(feature = "bz2"))]
+    fn compress_bz2(_: &[u8]) -> Result<Vec<u8>, Error> {
+        Err(Error::from(InvalidExtension::Bz2NotCompiled))
+    }
+
+    #[cfg(feature = "lz4")]
+    fn compress_lz4(data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut compressor = lz4_flex::frame::FrameEncoder::new(Vec::new());
+        compressor.write_all(data).map_err(Error::Writer)?;
+        compressor
+            .finish()
+            .map_err(|e| Error::Writer(std::io::Error::other(e)))
+    }
+
+    #[cfg(not(feature = "lz4"))]
+    fn compress_lz4(_: &[u8]) -> Result<Vec<u8>, Error> {
+        Err(Error::from(InvalidExtension::Lz4NotCompiled))
+    }
+}
+
+/// writes the elements of a root list started with `Encoder::list_writer`
+pub struct ListWriter<'a, W: Write> {
+    encoder: &'a mut Encoder<W>,
+    declared: Option<usize>,
+    written: usize,
+}
+
+impl<W: Write> ListWriter<'_, W> {
+    pub fn push(&mut self, item: &Item) -> Result<(), Error> {
+        if let Some(declared) = self.declared {
+            if self.written == declared {
+                return Err(Error::LengthMismatch {
+                    declared,
+                    written: declared + 1,
+                });
+            }
+        }
+        self.encoder.write_item(item)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// checks that the declared number of elements was written and flushes the writer
+    pub fn finish(self) -> Result<(), Error> {
+        if let Some(declared) = self.declared {
+            if self.written != declared {
+                return Err(Error::LengthMismatch {
+                    declared,
+                    written: self.written,
+                });
+            }
+        }
+        self.encoder.writer.flush().map_err(Error::Writer)
+    }
+}
+
+#[cfg(test)]
+fn round_trip(data: &[u8]) -> Vec<u8> {
+    use crate::Parser;
+
+    let item = Parser::new(data)
+        .parse()
+        .unwrap()
+        .expect("fixture contains an item");
+
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer).write(&item).unwrap();
+
+    let parsed = Parser::new(buffer.as_slice()).parse().unwrap();
+    assert_eq!(parsed, Some(item));
+
+    buffer
+}
+
+#[test]
+fn writes_header() {
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer).write(&Item::Void).unwrap();
+
+    assert_eq!(buffer, b"BSDF\x02\x02v");
+}
+
+#[test]
+fn writes_reference_version() {
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer).write(&Item::Bool(true)).unwrap();
+    assert_eq!(buffer, b"BSDF\x02\x02y");
+
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer)
+        .version(2, 1)
+        .write(&Item::Bool(true))
+        .unwrap();
+    assert_eq!(buffer, b"BSDF\x02\x01y");
+}
+
+#[test]
+fn writes_scalars() {
+    let mut buffer = Vec::new();
+    let item = Item::List(vec![
+        Item::Void,
+        Item::Bool(true),
+        Item::Bool(false),
+        Item::Int16(-1),
+        Item::Int64(123456789),
+        Item::F32(1.5),
+    ]);
+    Encoder::new(&mut buffer).write(&item).unwrap();
+
+    assert_eq!(
+        buffer,
+        b"BSDF\x02\x02l\x06vynh\xff\xffi\x15\xcd[\x07\x00\x00\x00\x00f\x00\x00\xc0?"
+    );
+}
+
+#[test]
+fn writes_large_size() {
+    let text = "x".repeat(300);
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer)
+        .write(&Item::String(text.clone()))
+        .unwrap();
+
+    assert_eq!(&buffer[6..16], b"s\xfd\x2c\x01\x00\x00\x00\x00\x00\x00");
+    assert_eq!(&buffer[16..], text.as_bytes());
+}
+
+#[test]
+fn writes_size_boundary() {
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer)
+        .write(&Item::String("x".repeat(250)))
+        .unwrap();
+    assert_eq!(&buffer[6..8], b"s\xfa");
+
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer)
+        .write(&Item::String("x".repeat(251)))
+        .unwrap();
+    assert_eq!(&buffer[6..16], b"s\xfd\xfb\x00\x00\x00\x00\x00\x00\x00");
+}
+
+#[test]
+fn round_trip_keys() {
+    let item = Item::map([
+        (String::new(), Item::Int16(1)),
+        ("k".repeat(300), Item::Int16(2)),
+    ]);
+
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer).write(&item).unwrap();
+
+    let parsed = crate::Parser::new(buffer.as_slice()).parse().unwrap();
+    assert_eq!(parsed, Some(item));
+}
+
+#[test]
+fn writes_compact_integers() {
+    let item = Item::Int64(5);
+
+    let mut exact = Vec::new();
+    Encoder::new(&mut exact)
+        .int_encoding(IntEncoding::Exact)
+        .write(&item)
+        .unwrap();
+
+    let mut compact = Vec::new();
+    Encoder::new(&mut compact)
+        .int_encoding(IntEncoding::Compact)
+        .write(&item)
+        .unwrap();
+
+    assert_eq!(exact.len(), 15);
+    assert_eq!(compact.len(), 9);
+    assert_eq!(compact, b"BSDF\x02\x02h\x05\x00");
+
+    // values that don't fit stay int64
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer)
+        .int_encoding(IntEncoding::Compact)
+        .write(&Item::Int64(100_000))
+        .unwrap();
+
+    assert_eq!(buffer.len(), 15);
+}
+
+#[allow(clippy::approx_constant)]
+#[test]
+fn writes_compact_floats() {
+    let item = Item::List(vec![Item::F64(1.0), Item::F64(3.14), Item::F64(-0.0)]);
+
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer)
+        .float_encoding(FloatEncoding::Compact)
+        .write(&item)
+        .unwrap();
+    let parsed = crate::Parser::new(buffer.as_slice()).parse().unwrap();
+    assert_eq!(
+        parsed,
+        Some(Item::List(vec![
+            Item::F32(1.0),
+            Item::F64(3.14),
+            Item::F32(-0.0)
+        ]))
+    );
+
+    // exact by default
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer).write(&item).unwrap();
+    let parsed = crate::Parser::new(buffer.as_slice()).parse().unwrap();
+    assert_eq!(parsed, Some(item));
+}
+
+#[test]
+fn round_trip_float64() {
+    let data = b"BSDF\x02\x02do\x12\x83\xc0\xca!\t@";
+
+    assert_eq!(round_trip(data), data);
+}
+
+#[test]
+fn round_trip_string() {
+    let data = b"BSDF\x02\x02s\xfd\xc9\x02\x00\x00\x00\x00\x00\x00\nLorem ipsum dolor sit amet, consectetur adipiscing elit. Duis id ante velit. Aenean euismod, ipsum a varius finibus, eros erat tincidunt ligula, non malesuada ex ipsum et tellus. Cras id convallis mauris, mattis porttitor nulla. In urna orci, faucibus ut consequat eleifend, vulputate ac elit. Integer gravida porta arcu, id volutpat libero lobortis at. Aenean bibendum eleifend auctor. Sed lectus purus, aliquet non purus ut, feugiat tristique leo. Praesent ut leo blandit, vulputate ex sit amet, venenatis libero. Curabitur vehicula ut enim sed posuere. Aliquam nec elit fringilla, aliquet lectus sed, suscipit quam. Vivamus malesuada ligula eu luctus finibus. Proin euismod sem sit amet eros euismod rhoncus.\n";
+
+    assert_eq!(round_trip(data), data);
+}
+
+#[test]
+fn round_trip_normal_map() {
+    // key order of the map is not stable, so only the length can be compared
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+
+    assert_eq!(round_trip(data).len(), data.len());
+}
+
+#[test]
+fn round_trip_nested_map() {
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+
+    assert_eq!(round_trip(data).len(), data.len());
+}
+
+#[test]
+fn round_trip_blob() {
+    let data = b"BSDF\x02\x02b\n\n\n\x00\xff\x7fc\xcbm\x06yr\xc3\xf3O\tK\xb7\xe7v\xa8\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00";
+
+    // the reference writer omits the checksum by default, which shifts the alignment
+    let expected =
+        b"BSDF\x02\x02b\n\n\n\x00\x00\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00";
+
+    assert_eq!(round_trip(data), expected);
+}
+
+#[test]
+fn writes_canonical() {
+    let keys: Vec<String> = (0..32).map(|i| alloc::format!("key{}", i)).collect();
+    let entry = |key: &String| (key.clone(), Item::map([(key.clone(), Item::Void)]));
+    let forward = Item::map(keys.iter().map(entry));
+    let backward = Item::map(keys.iter().rev().map(entry));
+
+    let write = |item: &Item| {
+        let mut buffer = Vec::new();
+        Encoder::new(&mut buffer)
+            .canonical(true)
+            .write(item)
+            .unwrap();
+        buffer
+    };
+    let bytes = write(&forward);
+    assert_eq!(bytes, write(&forward));
+    assert_eq!(bytes, write(&backward));
+
+    // "key0", "key1", "key10", ... in that order
+    let position = |key: &str| {
+        bytes
+            .windows(key.len())
+            .position(|window| window == key.as_bytes())
+    };
+    assert!(position("key0") < position("key1"));
+    assert!(position("key19") < position("key2"));
+}
+
+#[test]
+fn round_trip_blob_without_checksum() {
+    use crate::Parser;
+
+    let item = Item::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer)
+        .blob_checksum(true)
+        .blob_checksum(false)
+        .write(&item)
+        .unwrap();
+    // checksum byte 0x00 after the three sizes and the compression byte, no hash after it
+    assert_eq!(
+        buffer,
+        b"BSDF\x02\x02b\n\n\n\x00\x00\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00"
+    );
+
+    let parsed = Parser::new(buffer.as_slice())
+        .verify_checksums(true)
+        .parse()
+        .unwrap();
+    assert_eq!(parsed, Some(item));
+}
+
+#[test]
+fn round_trip_blob_allocated() {
+    use crate::Parser;
+
+    let data = [1, 2, 3, 4, 5, 6, 7, 8, 9, 0];
+    let meta = BlobMeta {
+        allocated_size: 32,
+        used_size: 10,
+        data_size: 10,
+        compression: consts::COMPRESSION_NOT_SET,
+        checksum_setting: consts::CHECKSUM_NOT_SET,
+        checksum: None,
+        alignment: 0,
+    };
+
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer)
+        .write_blob_raw(&meta, &data)
+        .unwrap();
+    assert!(buffer.ends_with(&[0; 22]));
+
+    let (parsed, blob) = Parser::new(buffer.as_slice()).parse_blob_raw().unwrap();
+    assert_eq!(parsed.allocated_size, 32);
+    assert_eq!(parsed.used_size, 10);
+    assert_eq!(blob, data);
+
+    let item = Parser::new(buffer.as_slice()).parse().unwrap();
+    assert_eq!(item, Some(Item::Blob(data.to_vec())));
+}
+
+#[test]
+fn writes_blob_raw_invalid() {
+    let meta = BlobMeta {
+        allocated_size: 4,
+        used_size: 10,
+        data_size: 10,
+        compression: consts::COMPRESSION_NOT_SET,
+        checksum_setting: consts::CHECKSUM_NOT_SET,
+        checksum: None,
+        alignment: 0,
+    };
+
+    let mut buffer = Vec::new();
+    let mut encoder = Encoder::new(&mut buffer);
+    assert_eq!(
+        encoder.write_blob_raw(&meta, &[0; 10]),
+        Err(Error::InvalidBlob)
+    );
+    assert_eq!(
+        encoder.write_blob_raw(&meta, &[0; 3]),
+        Err(Error::LengthMismatch {
+            declared: 10,
+            written: 3
+        })
+    );
+}
+
+#[test]
+fn round_trip_float_bits() {
+    use crate::Parser;
+
+    // floats are written and read with to_le_bytes/from_le_bytes, which never touch the bits
+    let doubles = [
+        f64::NAN,
+        -f64::NAN,
+        f64::from_bits(0x7ff0_0000_dead_beef),
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        -0.0,
+    ];
+    let floats = [
+        f32::NAN,
+        f32::from_bits(0x7f80_beef),
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        -0.0,
+    ];
+
+    for value in doubles {
+        let mut buffer = Vec::new();
+        Encoder::new(&mut buffer).write(&Item::F64(value)).unwrap();
+        let parsed = Parser::new(buffer.as_slice()).parse().unwrap().unwrap();
+
+        assert_eq!(parsed.as_f64().map(|x| x.to_bits()), Some(value.to_bits()));
+    }
+
+    for value in floats {
+        let mut buffer = Vec::new();
+        Encoder::new(&mut buffer).write(&Item::F32(value)).unwrap();
+        let parsed = Parser::new(buffer.as_slice()).parse().unwrap().unwrap();
+
+        assert_eq!(parsed.as_f32().map(|x| x.to_bits()), Some(value.to_bits()));
+    }
+
+    assert_ne!(Item::F64(f64::NAN), Item::F64(f64::NAN));
+}
+
+#[test]
+fn writes_list_stream() {
+    use crate::Parser;
+
+    let mut buffer = Vec::new();
+    let mut encoder = Encoder::new(&mut buffer);
+    let mut list = encoder.list_writer(None).unwrap();
+    for n in 0..1000 {
+        list.push(&Item::Int64(n)).unwrap();
+    }
+    list.finish().unwrap();
+
+    let expected = Item::List((0..1000).map(Item::Int64).collect());
+    assert_eq!(Parser::new(buffer.as_slice()).parse(), Ok(Some(expected)));
+}
+
+#[test]
+fn writes_list_with_length() {
+    use crate::Parser;
+
+    let mut buffer = Vec::new();
+    let mut encoder = Encoder::new(&mut buffer);
+    let mut list = encoder.list_writer(Some(2)).unwrap();
+    list.push(&Item::Bool(true)).unwrap();
+    list.push(&Item::Void).unwrap();
+    assert_eq!(
+        list.push(&Item::Void),
+        Err(Error::LengthMismatch {
+            declared: 2,
+            written: 3
+        })
+    );
+    list.finish().unwrap();
+
+    assert_eq!(buffer, b"BSDF\x02\x02l\x02yv");
+    assert_eq!(
+        Parser::new(buffer.as_slice()).parse(),
+        Ok(Some(Item::List(vec![Item::Bool(true), Item::Void])))
+    );
+}
+
+#[test]
+fn writes_list_too_short() {
+    let mut buffer = Vec::new();
+    let mut encoder = Encoder::new(&mut buffer);
+    let mut list = encoder.list_writer(Some(2)).unwrap();
+    list.push(&Item::Void).unwrap();
+
+    assert_eq!(
+        list.finish(),
+        Err(Error::LengthMismatch {
+            declared: 2,
+            written: 1
+        })
+    );
+}
+
+#[cfg(not(feature = "zlib"))]
+#[test]
+fn writes_zlib_not_compiled() {
+    let mut buffer = Vec::new();
+    let result = Encoder::new(&mut buffer)
+        .compression(Compression::Zlib)
+        .write(&Item::Blob(vec![1, 2, 3]));
+
+    assert_eq!(result, Err(Error::from(InvalidExtension::ZlibNotCompiled)));
+}
+
+#[cfg(all(test, feature = "zlib"))]
+mod zlib_test {
+    use super::*;
+
+    #[test]
+    fn writes_blob_zlib() {
+        use crate::Parser;
+
+        let item = Item::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+
+        let mut buffer = Vec::new();
+        Encoder::new(&mut buffer)
+            .compression(Compression::Zlib)
+            .write(&item)
+            .unwrap();
+
+        // compression byte directly follows the three sizes
+        assert_eq!(buffer[10], consts::COMPRESSION_ZLIB);
+        // data size is the uncompressed length
+        assert_eq!(buffer[9], 10);
+
+        let parsed = Parser::new(buffer.as_slice()).parse().unwrap();
+        assert_eq!(parsed, Some(item));
+    }
+
+    #[test]
+    fn round_trip_blob_zlib() {
+        let data = b"BSDF\x02\x02b\xfd\x12\x00\x00\x00\x00\x00\x00\x00\xfd\x12\x00\x00\x00\x00\x00\x00\x00\xfd\n\x00\x00\x00\x00\x00\x00\x00\x01\xff\x01&\xdcT\xa3\xfcr\x7f\x1f\x14sM\xb66\x05i\x00x\xdacdbfaec\xe7\xe0d\x00\x00\x00\xdc\x00.";
+
+        round_trip(data);
+    }
+}
+
+#[cfg(not(feature = "lz4"))]
+#[test]
+fn writes_lz4_not_compiled() {
+    let mut buffer = Vec::new();
+    let result = Encoder::new(&mut buffer)
+        .compression(Compression::Lz4)
+        .write(&Item::Blob(vec![1, 2, 3]));
+
+    assert_eq!(result, Err(Error::from(InvalidExtension::Lz4NotCompiled)));
+}
+
+#[test]
+fn writes_invalid_checksum() {
+    let mut buffer = Vec::new();
+    let result = Encoder::new(&mut buffer)
+        .checksum(42)
+        .write(&Item::Blob(vec![1, 2, 3]));
+
+    assert_eq!(
+        result,
+        Err(Error::from(InvalidExtension::InvalidChecksumSetting(42)))
+    );
+}
+
+#[cfg(not(feature = "crc32"))]
+#[test]
+fn writes_crc32_not_compiled() {
+    let mut buffer = Vec::new();
+    let result = Encoder::new(&mut buffer)
+        .checksum(consts::CHECKSUM_CRC32)
+        .write(&Item::Blob(vec![1, 2, 3]));
+
+    assert_eq!(result, Err(Error::from(InvalidExtension::Crc32NotCompiled)));
+}
+
+#[cfg(all(test, feature = "crc32"))]
+mod crc32_test {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn round_trip_blob_crc32() {
+        let item = Item::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+
+        let mut buffer = Vec::new();
+        Encoder::new(&mut buffer)
+            .checksum(consts::CHECKSUM_CRC32)
+            .write(&item)
+            .unwrap();
+
+        // checksum byte follows the three sizes and the compression byte
+        assert_eq!(buffer[11], consts::CHECKSUM_CRC32);
+        assert_eq!(
+            buffer[12..16],
+            crc32fast::hash(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 0]).to_le_bytes()
+        );
+
+        let (meta, _) = Parser::new(buffer.as_slice()).parse_blob_raw().unwrap();
+        assert_eq!(meta.checksum_setting, consts::CHECKSUM_CRC32);
+
+        let parsed = Parser::new(buffer.as_slice())
+            .verify_checksums(true)
+            .parse()
+            .unwrap();
+        assert_eq!(parsed, Some(item));
+    }
+
+    #[test]
+    fn blob_crc32_mismatch() {
+        let mut buffer = Vec::new();
+        Encoder::new(&mut buffer)
+            .checksum(consts::CHECKSUM_CRC32)
+            .write(&Item::Blob(vec![1, 2, 3]))
+            .unwrap();
+        *buffer.last_mut().unwrap() = 4;
+
+        let result = Parser::new(buffer.as_slice())
+            .verify_checksums(true)
+            .parse()
+            .map_err(Error::into_inner);
+        assert_eq!(result, Err(Error::InvalidBlobHash));
+    }
+}
+
+#[cfg(all(test, feature = "md5"))]
+mod md5_test {
+    use super::*;
+
+    #[test]
+    fn round_trip_blob_md5() {
+        use crate::Parser;
+
+        let item = Item::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+
+        let mut buffer = Vec::new();
+        Encoder::new(&mut buffer)
+            .checksum(consts::CHECKSUM_SET)
+            .write(&item)
+            .unwrap();
+
+        // same bytes as the blob fixture written by the reference implementation
+        assert_eq!(buffer, b"BSDF\x02\x02b\n\n\n\x00\xff\x7fc\xcbm\x06yr\xc3\xf3O\tK\xb7\xe7v\xa8\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00");
+
+        let parsed = Parser::new(buffer.as_slice())
+            .verify_checksums(true)
+            .parse()
+            .unwrap();
+        assert_eq!(parsed, Some(item));
+    }
+
+    #[test]
+    fn round_trip_blob_checksum() {
+        use crate::Parser;
+
+        let item = Item::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+
+        let mut buffer = Vec::new();
+        Encoder::new(&mut buffer)
+            .blob_checksum(true)
+            .write(&item)
+            .unwrap();
+        let (meta, _) = Parser::new(buffer.as_slice()).parse_blob_raw().unwrap();
+        assert_eq!(meta.checksum_setting, consts::CHECKSUM_SET);
+        assert_eq!(meta.checksum.map(|hash| hash.len()), Some(16));
+
+        let parsed = Parser::new(buffer.as_slice())
+            .verify_checksums(true)
+            .parse()
+            .unwrap();
+        assert_eq!(parsed, Some(item));
+    }
+}
+
+#[cfg(all(test, feature = "lz4"))]
+mod lz4_test {
+    use super::*;
+
+    #[test]
+    fn writes_blob_lz4() {
+        use crate::Parser;
+
+        let item = Item::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+
+        let mut buffer = Vec::new();
+        Encoder::new(&mut buffer)
+            .compression(Compression::Lz4)
+            .write(&item)
+            .unwrap();
+
+        assert_eq!(buffer[10], consts::COMPRESSION_LZ4);
+
+        let parsed = Parser::new(buffer.as_slice()).parse().unwrap();
+        assert_eq!(parsed, Some(item));
+    }
+}
+
+#[cfg(all(test, feature = "bz2"))]
+mod bz2_test {
+    use super::*;
+
+    #[test]
+    fn writes_blob_bz2() {
+        use crate::Parser;
+
+        let item = Item::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+
+        let mut buffer = Vec::new();
+        Encoder::new(&mut buffer)
+            .compression(Compression::Bz2)
+            .write(&item)
+            .unwrap();
+
+        let parsed = Parser::new(buffer.as_slice()).parse().unwrap();
+        assert_eq!(parsed, Some(item));
+    }
+
+    #[test]
+    fn round_trip_blob_bz2() {
+        let data = b"BSDF\x02\x02b\xfd-\x00\x00\x00\x00\x00\x00\x00\xfd-\x00\x00\x00\x00\x00\x00\x00\xfd\n\x00\x00\x00\x00\x00\x00\x00\x02\xff\xba9+d\xdd\x11\xba.\x1b\xa5\xddo\xde\x97l}\x00BZh91AY&SYTH\x0c\xaa\x00\x00\x00\xc0\x00\x7f\xe0 \x00\"\x01\xa6\x98@\x0c\x15^h\xe3\xe9\x8b\xb9\"\x9c(H*$\x06U\x00";
+
+        round_trip(data);
+    }
+}
@@ -0,0 +1,362 @@
+// serializes straight into the stream without building an `Item` first, the layout is the same as
+// `to_item` followed by `Encoder::write`. lists and maps need their length up front, the few
+// without a known length are built as an `Item` and written at the end
+
+use super::Encoder;
+use crate::item::ser::{self as item_ser, KeySerializer};
+use crate::{Error, Item};
+use serde::ser::{self, Serialize, SerializeSeq as _};
+use std::io::Write;
+
+impl<W: Write> Encoder<W> {
+    /// writes the header and `value`, then flushes
+    pub(crate) fn serialize<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.write_header()?;
+        value.serialize(&mut *self)?;
+        self.writer.flush().map_err(Error::Writer)
+    }
+
+    /// the first part of an enum variant with data, a map with the variant name as the only key
+    fn write_variant(&mut self, variant: &str) -> Result<(), Error> {
+        self.write_bytes(b"m")?;
+        self.write_size(1)?;
+        self.write_string(variant)
+    }
+}
+
+impl<'a, W: Write> ser::Serializer for &'a mut Encoder<W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = SerializeList<'a, W>;
+    type SerializeTuple = SerializeList<'a, W>;
+    type SerializeTupleStruct = SerializeList<'a, W>;
+    type SerializeTupleVariant = SerializeList<'a, W>;
+    type SerializeMap = SerializeMap<'a, W>;
+    type SerializeStruct = SerializeMap<'a, W>;
+    type SerializeStructVariant = SerializeMap<'a, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.write_item(&Item::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.write_item(&Item::Int16(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.write_item(&Item::Int16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.write_item(&Item::Int64(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.write_item(&Item::Int64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.write_item(&Item::Int16(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.write_item(&Item::Int64(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.write_item(&Item::Int64(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        let item = ser::Serializer::serialize_u64(item_ser::Serializer, v)?;
+        self.write_item(&item)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.write_item(&Item::F32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.write_item(&Item::F64(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.write_bytes(b"s")?;
+        self.write_string(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.write_blob(v)
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.write_bytes(b"v")
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.write_bytes(b"v")
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.write_bytes(b"v")
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.write_variant(variant)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList<'a, W>, Error> {
+        let buffer = match len {
+            Some(len) => {
+                self.write_bytes(b"l")?;
+                self.write_size(len)?;
+                None
+            }
+            None => Some(ser::Serializer::serialize_seq(item_ser::Serializer, None)?),
+        };
+        Ok(SerializeList {
+            encoder: self,
+            buffer,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList<'a, W>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeList<'a, W>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeList<'a, W>, Error> {
+        self.write_variant(variant)?;
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap<'a, W>, Error> {
+        let buffer = match len {
+            Some(len) => {
+                self.write_bytes(b"m")?;
+                self.write_size(len)?;
+                None
+            }
+            None => Some(ser::Serializer::serialize_map(item_ser::Serializer, None)?),
+        };
+        Ok(SerializeMap {
+            encoder: self,
+            buffer,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeMap<'a, W>, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeMap<'a, W>, Error> {
+        self.write_variant(variant)?;
+        self.serialize_map(Some(len))
+    }
+}
+
+pub struct SerializeList<'a, W> {
+    encoder: &'a mut Encoder<W>,
+    // only set when the length was not known up front
+    buffer: Option<item_ser::SerializeList>,
+}
+
+impl<W: Write> SerializeList<'_, W> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        match &mut self.buffer {
+            Some(buffer) => buffer.serialize_element(value),
+            None => value.serialize(&mut *self.encoder),
+        }
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        match self.buffer {
+            Some(buffer) => self.encoder.write_item(&buffer.end()?),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> ser::SerializeSeq for SerializeList<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<W: Write> ser::SerializeTuple for SerializeList<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<W: Write> ser::SerializeTupleStruct for SerializeList<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<W: Write> ser::SerializeTupleVariant for SerializeList<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+pub struct SerializeMap<'a, W> {
+    encoder: &'a mut Encoder<W>,
+    // only set when the length was not known up front
+    buffer: Option<item_ser::SerializeMap>,
+}
+
+impl<W: Write> SerializeMap<'_, W> {
+    fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        self.encoder.write_string(key)?;
+        value.serialize(&mut *self.encoder)
+    }
+}
+
+impl<W: Write> ser::SerializeMap for SerializeMap<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        match &mut self.buffer {
+            Some(buffer) => buffer.serialize_key(key),
+            None => {
+                let key = key.serialize(KeySerializer)?;
+                self.encoder.write_string(&key)
+            }
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        match &mut self.buffer {
+            Some(buffer) => buffer.serialize_value(value),
+            None => value.serialize(&mut *self.encoder),
+        }
+    }
+
+    fn end(self) -> Result<(), Error> {
+        match self.buffer {
+            Some(buffer) => self.encoder.write_item(&buffer.end()?),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> ser::SerializeStruct for SerializeMap<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl<W: Write> ser::SerializeStructVariant for SerializeMap<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        ser::SerializeMap::end(self)
+    }
+}
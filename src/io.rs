@@ -0,0 +1,39 @@
+// the parser only pulls bytes out of a source. with std that is any `std::io::Read`, without std
+// only byte slices can be read and reading them can't fail, running out of data is `Error::Eof`
+
+#[cfg(feature = "std")]
+pub use std::io::{Error, Read, Result};
+
+#[cfg(not(feature = "std"))]
+pub use no_std::{Error, Read, Result};
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+    /// reading from a slice never fails, so there are no values of this error
+    #[derive(Debug)]
+    pub enum Error {}
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// the subset of `std::io::Read` the parser uses
+    pub trait Read {
+        /// same contract as `std::io::Read::read`, `Ok(0)` means the end of the data
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+    }
+
+    impl Read for &[u8] {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let n = buf.len().min(self.len());
+            let (data, rest) = self.split_at(n);
+            buf[..n].copy_from_slice(data);
+            *self = rest;
+            Ok(n)
+        }
+    }
+
+    impl<R: Read + ?Sized> Read for &mut R {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            (**self).read(buf)
+        }
+    }
+}
@@ -0,0 +1,76 @@
+// random items for fuzzing, lists and maps only nest a few levels so generating can't overflow
+// the stack and the items stay small
+
+use crate::{item, Item};
+use alloc::vec::Vec;
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+const MAX_DEPTH: usize = 4;
+const MAX_LENGTH: usize = 8;
+
+impl<'a> Arbitrary<'a> for Item {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Item> {
+        arbitrary_item(u, 0)
+    }
+}
+
+fn arbitrary_item(u: &mut Unstructured<'_>, depth: usize) -> Result<Item> {
+    // the collections are left out once the depth is reached
+    let kinds = if depth < MAX_DEPTH { 10 } else { 8 };
+    let item = match u.choose_index(kinds)? {
+        0 => Item::Void,
+        1 => Item::Bool(u.arbitrary()?),
+        2 => Item::Int16(u.arbitrary()?),
+        3 => Item::Int64(u.arbitrary()?),
+        4 => Item::F32(u.arbitrary()?),
+        5 => Item::F64(u.arbitrary()?),
+        6 => Item::String(u.arbitrary()?),
+        7 => Item::Blob(u.arbitrary()?),
+        8 => {
+            let length = u.int_in_range(0..=MAX_LENGTH)?;
+            let mut list = Vec::with_capacity(length);
+            for _ in 0..length {
+                list.push(arbitrary_item(u, depth + 1)?);
+            }
+            Item::List(list)
+        }
+        _ => {
+            let length = u.int_in_range(0..=MAX_LENGTH)?;
+            let mut map = item::map_with_capacity(length);
+            for _ in 0..length {
+                map.insert(u.arbitrary()?, arbitrary_item(u, depth + 1)?);
+            }
+            Item::Map(map)
+        }
+    };
+    Ok(item)
+}
+
+#[test]
+fn arbitrary_round_trip_test() {
+    use crate::HashableItem;
+
+    // xorshift, so every run checks the same items
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random_bytes = |n: usize| -> Vec<u8> {
+        (0..n)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    };
+
+    for _ in 0..300 {
+        let bytes = random_bytes(512);
+        let item = Item::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+
+        let data = crate::to_vec(&item).unwrap();
+        let parsed = crate::from_slice(&data).unwrap();
+
+        // compares floats by their bits, so NaN round trips too
+        assert_eq!(parsed.map(HashableItem), Some(HashableItem(item)));
+    }
+}
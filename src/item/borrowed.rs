@@ -0,0 +1,55 @@
+use crate::{Item, Map};
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+#[cfg(feature = "ordered-map")]
+use indexmap::IndexMap;
+#[cfg(all(feature = "std", not(feature = "ordered-map")))]
+use std::collections::HashMap;
+
+#[cfg(all(feature = "std", not(feature = "ordered-map")))]
+pub type BorrowedMap<'a> = HashMap<Cow<'a, str>, BorrowedItem<'a>>;
+#[cfg(feature = "ordered-map")]
+pub type BorrowedMap<'a> = IndexMap<Cow<'a, str>, BorrowedItem<'a>>;
+#[cfg(not(feature = "std"))]
+pub type BorrowedMap<'a> = BTreeMap<Cow<'a, str>, BorrowedItem<'a>>;
+
+/// same as `Item`, but strings and blobs point into the parsed slice where possible.
+/// compressed blobs are decompressed into an owned buffer
+#[derive(Debug, Clone, PartialEq)]
+pub enum BorrowedItem<'a> {
+    Map(BorrowedMap<'a>),
+    Blob(Cow<'a, [u8]>),
+    List(Vec<BorrowedItem<'a>>),
+    Int16(i16),
+    Int64(i64),
+    F32(f32),
+    F64(f64),
+    String(Cow<'a, str>),
+    Bool(bool),
+    Void,
+}
+
+impl BorrowedItem<'_> {
+    pub fn into_owned(self) -> Item {
+        match self {
+            BorrowedItem::Map(map) => Item::Map(
+                map.into_iter()
+                    .map(|(key, item)| (key.into_owned(), item.into_owned()))
+                    .collect::<Map>(),
+            ),
+            BorrowedItem::Blob(data) => Item::Blob(data.into_owned()),
+            BorrowedItem::List(list) => {
+                Item::List(list.into_iter().map(BorrowedItem::into_owned).collect())
+            }
+            BorrowedItem::Int16(n) => Item::Int16(n),
+            BorrowedItem::Int64(n) => Item::Int64(n),
+            BorrowedItem::F32(v) => Item::F32(v),
+            BorrowedItem::F64(v) => Item::F64(v),
+            BorrowedItem::String(text) => Item::String(text.into_owned()),
+            BorrowedItem::Bool(v) => Item::Bool(v),
+            BorrowedItem::Void => Item::Void,
+        }
+    }
+}
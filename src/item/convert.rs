@@ -0,0 +1,267 @@
+// integers keep the variant of their rust type, an i64 that would fit in an i16 is not narrowed,
+// so `Item::from(x)` always gives back the same variant for the same type.
+// the other way around integers and floats are widened (int16 -> i64, f32 -> f64) but never
+// narrowed lossy, an int64 only becomes an i16 if it is in range
+
+use crate::{Error, Item, Map};
+use alloc::string::String;
+#[cfg(test)]
+use alloc::vec;
+use alloc::vec::Vec;
+
+impl From<i16> for Item {
+    fn from(value: i16) -> Item {
+        Item::Int16(value)
+    }
+}
+
+impl From<i64> for Item {
+    fn from(value: i64) -> Item {
+        Item::Int64(value)
+    }
+}
+
+impl From<f32> for Item {
+    fn from(value: f32) -> Item {
+        Item::F32(value)
+    }
+}
+
+impl From<f64> for Item {
+    fn from(value: f64) -> Item {
+        Item::F64(value)
+    }
+}
+
+impl From<bool> for Item {
+    fn from(value: bool) -> Item {
+        Item::Bool(value)
+    }
+}
+
+impl From<String> for Item {
+    fn from(value: String) -> Item {
+        Item::String(value)
+    }
+}
+
+impl From<&str> for Item {
+    fn from(value: &str) -> Item {
+        Item::String(String::from(value))
+    }
+}
+
+impl From<Vec<u8>> for Item {
+    fn from(value: Vec<u8>) -> Item {
+        Item::Blob(value)
+    }
+}
+
+impl From<Vec<Item>> for Item {
+    fn from(value: Vec<Item>) -> Item {
+        Item::List(value)
+    }
+}
+
+impl From<Map> for Item {
+    fn from(value: Map) -> Item {
+        Item::Map(value)
+    }
+}
+
+impl FromIterator<Item> for Item {
+    fn from_iter<I: IntoIterator<Item = Item>>(iter: I) -> Item {
+        Item::List(iter.into_iter().collect())
+    }
+}
+
+impl FromIterator<(String, Item)> for Item {
+    fn from_iter<I: IntoIterator<Item = (String, Item)>>(iter: I) -> Item {
+        Item::Map(iter.into_iter().collect())
+    }
+}
+
+impl TryFrom<Item> for i16 {
+    type Error = Error;
+
+    fn try_from(item: Item) -> Result<i16, Error> {
+        match item {
+            Item::Int16(n) => Ok(n),
+            Item::Int64(n) => i16::try_from(n).map_err(|_| Error::IntegerOutOfRange(n)),
+            other => Err(Error::type_mismatch("int16", &other)),
+        }
+    }
+}
+
+impl TryFrom<Item> for i64 {
+    type Error = Error;
+
+    fn try_from(item: Item) -> Result<i64, Error> {
+        match item {
+            Item::Int16(n) => Ok(n.into()),
+            Item::Int64(n) => Ok(n),
+            other => Err(Error::type_mismatch("int64", &other)),
+        }
+    }
+}
+
+impl TryFrom<Item> for f64 {
+    type Error = Error;
+
+    fn try_from(item: Item) -> Result<f64, Error> {
+        match item {
+            Item::F32(v) => Ok(v.into()),
+            Item::F64(v) => Ok(v),
+            other => Err(Error::type_mismatch("f64", &other)),
+        }
+    }
+}
+
+impl TryFrom<Item> for bool {
+    type Error = Error;
+
+    fn try_from(item: Item) -> Result<bool, Error> {
+        match item {
+            Item::Bool(v) => Ok(v),
+            other => Err(Error::type_mismatch("bool", &other)),
+        }
+    }
+}
+
+impl TryFrom<Item> for String {
+    type Error = Error;
+
+    fn try_from(item: Item) -> Result<String, Error> {
+        match item {
+            Item::String(v) => Ok(v),
+            other => Err(Error::type_mismatch("string", &other)),
+        }
+    }
+}
+
+impl TryFrom<Item> for Vec<u8> {
+    type Error = Error;
+
+    fn try_from(item: Item) -> Result<Vec<u8>, Error> {
+        match item {
+            Item::Blob(v) => Ok(v),
+            Item::CompressedBlob(blob) => Ok(blob.data),
+            other => Err(Error::type_mismatch("blob", &other)),
+        }
+    }
+}
+
+/// parses a whole document like `from_slice`, an empty document is `Error::MissingData`. the
+/// orphan rule doesn't allow the same for `Option<Item>`, `from_slice` is that form
+impl TryFrom<&[u8]> for Item {
+    type Error = Error;
+
+    fn try_from(data: &[u8]) -> Result<Item, Error> {
+        crate::from_slice(data)?.ok_or(Error::MissingData)
+    }
+}
+
+#[test]
+#[allow(clippy::approx_constant)]
+fn from_test() {
+    assert_eq!(Item::from(3.14f64), Item::F64(3.14));
+    assert_eq!(Item::from(1.5f32), Item::F32(1.5));
+    assert_eq!(Item::from("hi"), Item::String(String::from("hi")));
+    assert_eq!(
+        Item::from(String::from("hi")),
+        Item::String(String::from("hi"))
+    );
+    assert_eq!(Item::from(3i16), Item::Int16(3));
+    assert_eq!(Item::from(3i64), Item::Int64(3));
+    assert_eq!(Item::from(true), Item::Bool(true));
+    assert_eq!(Item::from(vec![1u8, 2]), Item::Blob(vec![1, 2]));
+    assert_eq!(
+        Item::from(vec![Item::from(1i64)]),
+        Item::List(vec![Item::Int64(1)])
+    );
+    assert_eq!(
+        Item::from(Map::from_iter([(String::from("a"), Item::from(false))])),
+        Item::Map(Map::from_iter([(String::from("a"), Item::Bool(false))]))
+    );
+}
+
+#[test]
+fn try_from_test() {
+    assert_eq!(i64::try_from(Item::Int64(5)), Ok(5));
+    assert_eq!(i64::try_from(Item::Int16(-5)), Ok(-5));
+    assert_eq!(i16::try_from(Item::Int64(5)), Ok(5));
+    assert_eq!(
+        i16::try_from(Item::Int64(100_000)),
+        Err(Error::IntegerOutOfRange(100_000))
+    );
+    assert_eq!(f64::try_from(Item::F64(1.5)), Ok(1.5));
+    assert_eq!(f64::try_from(Item::F32(1.5)), Ok(1.5));
+    assert_eq!(bool::try_from(Item::Bool(true)), Ok(true));
+    assert_eq!(
+        String::try_from(Item::String(String::from("hi"))),
+        Ok(String::from("hi"))
+    );
+    assert_eq!(Vec::<u8>::try_from(Item::Blob(vec![1, 2])), Ok(vec![1, 2]));
+}
+
+#[test]
+fn try_from_bytes_test() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+
+    assert_eq!(
+        Item::try_from(data.as_slice()),
+        Ok(Item::Map(Map::from_iter([
+            (String::from("test"), Item::Int16(1)),
+            (String::from("test1"), Item::Int16(2)),
+            (String::from("test3"), Item::Int16(4)),
+        ])))
+    );
+    assert_eq!(
+        Item::try_from(b"BSDF\x02\x02".as_slice()),
+        Err(Error::MissingData)
+    );
+    assert_eq!(
+        Item::try_from(b"BSDF\x02\x02x".as_slice()).map_err(Error::into_inner),
+        Err(Error::InvalidType(b'x'))
+    );
+}
+
+#[test]
+fn try_from_mismatch_test() {
+    assert_eq!(
+        i64::try_from(Item::String(String::from("5"))),
+        Err(Error::TypeMismatch {
+            expected: "int64",
+            found: "string"
+        })
+    );
+    assert_eq!(
+        String::try_from(Item::Int16(5)),
+        Err(Error::TypeMismatch {
+            expected: "string",
+            found: "int16"
+        })
+    );
+    assert_eq!(
+        f64::try_from(Item::Void),
+        Err(Error::TypeMismatch {
+            expected: "f64",
+            found: "void"
+        })
+    );
+    assert_eq!(
+        bool::try_from(Item::Int16(1)),
+        Err(Error::TypeMismatch {
+            expected: "bool",
+            found: "int16"
+        })
+    );
+    assert_eq!(
+        Vec::<u8>::try_from(Item::List(vec![])),
+        Err(Error::TypeMismatch {
+            expected: "blob",
+            found: "list"
+        })
+    );
+}
@@ -0,0 +1,200 @@
+// deserializes any `Deserialize` type out of an `Item` tree, the counterpart of `ser.rs`
+
+use crate::{Error, Item, Map};
+use serde::de::value::StringDeserializer;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+impl de::Error for Error {
+    fn custom<T: core::fmt::Display>(msg: T) -> Error {
+        Error::Serde(msg.to_string())
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Item {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Item::Void => visitor.visit_unit(),
+            Item::Bool(v) => visitor.visit_bool(v),
+            Item::Int16(n) => visitor.visit_i16(n),
+            Item::Int64(n) => visitor.visit_i64(n),
+            Item::F32(v) => visitor.visit_f32(v),
+            Item::F64(v) => visitor.visit_f64(v),
+            Item::String(text) => visitor.visit_string(text),
+            Item::Blob(data) => visitor.visit_byte_buf(data),
+            Item::CompressedBlob(blob) => visitor.visit_byte_buf(blob.data),
+            Item::List(list) => visitor.visit_seq(SeqDeserializer(list.into_iter())),
+            Item::Map(map) => visitor.visit_map(MapDeserializer::new(map)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Item::Void => visitor.visit_none(),
+            item => visitor.visit_some(item),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Item::String(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: None,
+            }),
+            Item::Map(map) if map.len() == 1 => {
+                let (variant, value) = map.into_iter().next().expect("map has one entry");
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            other => Err(Error::type_mismatch("enum", &other)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl IntoDeserializer<'_, Error> for Item {
+    type Deserializer = Item;
+
+    fn into_deserializer(self) -> Item {
+        self
+    }
+}
+
+struct SeqDeserializer(alloc::vec::IntoIter<Item>);
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.0.next().map(|item| seed.deserialize(item)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct MapDeserializer {
+    entries: <Map as IntoIterator>::IntoIter,
+    value: Option<Item>,
+}
+
+impl MapDeserializer {
+    fn new(map: Map) -> MapDeserializer {
+        MapDeserializer {
+            entries: map.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                let key: StringDeserializer<Error> = key.into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Error::Serde(String::from("map value without a key")))?;
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: Option<Item>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = Error;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantDeserializer), Error> {
+        let variant: StringDeserializer<Error> = self.variant.into_deserializer();
+        let variant = seed.deserialize(variant)?;
+        Ok((variant, VariantDeserializer(self.value)))
+    }
+}
+
+struct VariantDeserializer(Option<Item>);
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.0 {
+            None | Some(Item::Void) => Ok(()),
+            Some(other) => Err(Error::type_mismatch("void", &other)),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self.0.unwrap_or(Item::Void))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Some(Item::List(list)) => visitor.visit_seq(SeqDeserializer(list.into_iter())),
+            Some(other) => Err(Error::type_mismatch("list", &other)),
+            None => Err(Error::type_mismatch("list", &Item::Void)),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.0 {
+            Some(Item::Map(map)) => visitor.visit_map(MapDeserializer::new(map)),
+            Some(other) => Err(Error::type_mismatch("map", &other)),
+            None => Err(Error::type_mismatch("map", &Item::Void)),
+        }
+    }
+}
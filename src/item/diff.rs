@@ -0,0 +1,59 @@
+// the differences between two items as readable lines, one per differing leaf. floats compare by
+// their bits here so a NaN on both sides is not reported
+
+use crate::Item;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+pub(crate) fn diff(a: &Item, b: &Item, path: &mut String, out: &mut Vec<String>) {
+    match (a, b) {
+        (Item::Map(a), Item::Map(b)) => {
+            // sorted so the report does not depend on the map order
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort_unstable();
+            keys.dedup();
+
+            for key in keys {
+                let length = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                match (a.get(key.as_str()), b.get(key.as_str())) {
+                    (Some(a), Some(b)) => diff(a, b, path, out),
+                    (a, b) => report(a, b, path, out),
+                }
+                path.truncate(length);
+            }
+        }
+        (Item::List(a), Item::List(b)) => {
+            for index in 0..a.len().max(b.len()) {
+                let length = path.len();
+                write!(path, "[{}]", index).expect("writing to a string can't fail");
+                match (a.get(index), b.get(index)) {
+                    (Some(a), Some(b)) => diff(a, b, path, out),
+                    (a, b) => report(a, b, path, out),
+                }
+                path.truncate(length);
+            }
+        }
+        (Item::F32(x), Item::F32(y)) if x.to_bits() == y.to_bits() => {}
+        (Item::F64(x), Item::F64(y)) if x.to_bits() == y.to_bits() => {}
+        (Item::F32(_), Item::F32(_)) | (Item::F64(_), Item::F64(_)) => {
+            report(Some(a), Some(b), path, out)
+        }
+        (a, b) if a == b => {}
+        (a, b) => report(Some(a), Some(b), path, out),
+    }
+}
+
+fn report(a: Option<&Item>, b: Option<&Item>, path: &str, out: &mut Vec<String>) {
+    let path = if path.is_empty() { "(root)" } else { path };
+    let show = |item: Option<&Item>| match item {
+        Some(item) => format!("{:?}", item),
+        None => String::from("<missing>"),
+    };
+    out.push(format!("{}: {} != {}", path, show(a), show(b)));
+}
@@ -0,0 +1,143 @@
+// readable rendering for debugging, it looks like json but blobs make it invalid json
+
+use crate::Item;
+use alloc::string::String;
+#[cfg(test)]
+use alloc::{format, string::ToString, vec, vec::Vec};
+use core::fmt::{self, Display, Formatter, Write};
+
+// strings longer than this are cut off in `debug_tree`
+const TREE_STRING_CHARS: usize = 40;
+
+impl Display for Item {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Void => write!(f, "null"),
+            Item::Bool(v) => write!(f, "{}", v),
+            Item::Int16(n) => write!(f, "{}", n),
+            Item::Int64(n) => write!(f, "{}", n),
+            Item::F32(v) => write!(f, "{}", v),
+            Item::F64(v) => write!(f, "{}", v),
+            Item::String(text) => write!(f, "{:?}", text),
+            Item::Blob(data) => write!(f, "<blob {} bytes>", data.len()),
+            Item::CompressedBlob(blob) => write!(f, "<blob {} bytes>", blob.data.len()),
+            Item::List(list) => {
+                write!(f, "[")?;
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Item::Map(map) => {
+                write!(f, "{{")?;
+                for (i, (key, item)) in map.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{:?}: {}", key, item)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// one line per value with its type, children are indented two spaces deeper than their parent
+pub(crate) fn write_tree(out: &mut String, item: &Item, depth: usize) -> fmt::Result {
+    write!(out, "{}", item.type_name())?;
+    match item {
+        Item::Void => {}
+        Item::Bool(v) => write!(out, " {}", v)?,
+        Item::Int16(n) => write!(out, " {}", n)?,
+        Item::Int64(n) => write!(out, " {}", n)?,
+        Item::F32(v) => write!(out, " {}", v)?,
+        Item::F64(v) => write!(out, " {}", v)?,
+        Item::String(text) => match text.char_indices().nth(TREE_STRING_CHARS) {
+            Some((end, _)) => write!(out, " {:?}...", &text[..end])?,
+            None => write!(out, " {:?}", text)?,
+        },
+        Item::Blob(data) => write!(out, " ({} bytes)", data.len())?,
+        Item::CompressedBlob(blob) => write!(out, " ({} bytes)", blob.data.len())?,
+        Item::List(list) => {
+            write!(out, " ({})", list.len())?;
+            for item in list {
+                write!(out, "\n{:indent$}", "", indent = (depth + 1) * 2)?;
+                write_tree(out, item, depth + 1)?;
+            }
+        }
+        Item::Map(map) => {
+            write!(out, " ({})", map.len())?;
+            for (key, item) in map {
+                write!(out, "\n{:indent$}{}: ", "", key, indent = (depth + 1) * 2)?;
+                write_tree(out, item, depth + 1)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn display_test() {
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+    let item = crate::from_slice(data).unwrap().unwrap();
+
+    let text = item.to_string();
+
+    assert!(text.starts_with('{') && text.ends_with('}'));
+    assert!(text.contains("\"test\": 1"));
+    assert!(text.contains("\"test1\": 2"));
+    assert!(text.contains("\"test3\": 4"));
+}
+
+#[test]
+fn display_values_test() {
+    let item = Item::List(vec![
+        Item::Void,
+        Item::Bool(true),
+        Item::F64(1.5),
+        Item::String(String::from("say \"hi\"\n")),
+        Item::Blob(vec![1, 2, 3]),
+        Item::List(vec![]),
+    ]);
+
+    assert_eq!(
+        item.to_string(),
+        r#"[null, true, 1.5, "say \"hi\"\n", <blob 3 bytes>, []]"#
+    );
+}
+
+#[test]
+fn debug_tree_test() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    let item = crate::from_slice(data).unwrap().unwrap();
+
+    let tree = crate::debug_tree(&item);
+    let lines: Vec<&str> = tree.lines().collect();
+
+    assert_eq!(lines[0], "map (2)");
+    assert!(lines.contains(&"  test: int16 1"));
+    assert!(lines.contains(&"  nested: map (3)"));
+    assert!(lines.contains(&"    nested: bool true"));
+    assert!(lines.contains(&"    list: list (3)"));
+    assert!(lines.contains(&"      int16 -1"));
+    assert!(lines.contains(&"      bool false"));
+    assert!(lines.contains(&"      int64 123456789"));
+    assert!(lines.contains(&"    data: string \"some text\""));
+}
+
+#[test]
+fn debug_tree_values_test() {
+    let item = Item::list([Item::Blob(vec![0; 12]), Item::from("x".repeat(50).as_str())]);
+
+    assert_eq!(
+        crate::debug_tree(&item),
+        format!(
+            "list (2)\n  blob (12 bytes)\n  string {:?}...",
+            "x".repeat(40)
+        )
+    );
+}
@@ -0,0 +1,123 @@
+// typed extraction without serde. the conversions follow `convert.rs`: numbers are widened but
+// only narrowed when the value is in range
+
+use crate::{Error, Item};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+#[cfg(test)]
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+/// conversion out of an `Item` that fails with `Error::TypeMismatch` or
+/// `Error::IntegerOutOfRange`, see `Parser::parse_typed`
+pub trait FromItem: Sized {
+    fn from_item(item: Item) -> Result<Self, Error>;
+}
+
+impl FromItem for Item {
+    fn from_item(item: Item) -> Result<Item, Error> {
+        Ok(item)
+    }
+}
+
+macro_rules! from_item_via_try_from {
+    ($($ty:ty),*) => {
+        $(
+            impl FromItem for $ty {
+                fn from_item(item: Item) -> Result<$ty, Error> {
+                    <$ty>::try_from(item)
+                }
+            }
+        )*
+    };
+}
+
+from_item_via_try_from!(i16, i64, f64, bool, String);
+
+macro_rules! from_item_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl FromItem for $ty {
+                fn from_item(item: Item) -> Result<$ty, Error> {
+                    let n = item.as_integer()?;
+                    <$ty>::try_from(n).map_err(|_| Error::IntegerOutOfRange(n))
+                }
+            }
+        )*
+    };
+}
+
+from_item_integer!(i8, i32, u8, u16, u32, u64, usize);
+
+/// only `F32`, an `F64` would lose precision
+impl FromItem for f32 {
+    fn from_item(item: Item) -> Result<f32, Error> {
+        match item {
+            Item::F32(v) => Ok(v),
+            other => Err(Error::type_mismatch("f32", &other)),
+        }
+    }
+}
+
+/// `Void` is `None`
+impl<T: FromItem> FromItem for Option<T> {
+    fn from_item(item: Item) -> Result<Option<T>, Error> {
+        match item {
+            Item::Void => Ok(None),
+            item => T::from_item(item).map(Some),
+        }
+    }
+}
+
+/// the elements of a list, a blob is not a list so `Vec<u8>` only takes lists here. use
+/// `Vec::<u8>::try_from` for blobs
+impl<T: FromItem> FromItem for Vec<T> {
+    fn from_item(item: Item) -> Result<Vec<T>, Error> {
+        item.try_into_list()?
+            .into_iter()
+            .map(T::from_item)
+            .collect()
+    }
+}
+
+impl<T: FromItem> FromItem for BTreeMap<String, T> {
+    fn from_item(item: Item) -> Result<BTreeMap<String, T>, Error> {
+        item.try_into_map()?
+            .into_iter()
+            .map(|(key, value)| Ok((key, T::from_item(value)?)))
+            .collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: FromItem> FromItem for HashMap<String, T> {
+    fn from_item(item: Item) -> Result<HashMap<String, T>, Error> {
+        item.try_into_map()?
+            .into_iter()
+            .map(|(key, value)| Ok((key, T::from_item(value)?)))
+            .collect()
+    }
+}
+
+#[test]
+fn from_item_test() {
+    assert_eq!(u8::from_item(Item::Int16(200)), Ok(200));
+    assert_eq!(
+        u8::from_item(Item::Int16(-1)),
+        Err(Error::IntegerOutOfRange(-1))
+    );
+    assert_eq!(Option::<bool>::from_item(Item::Void), Ok(None));
+    assert_eq!(
+        Vec::<i64>::from_item(Item::list([Item::Int16(1), Item::Int64(2)])),
+        Ok(vec![1, 2])
+    );
+    assert_eq!(
+        Vec::<String>::from_item(Item::list([Item::Int16(1)])),
+        Err(Error::TypeMismatch {
+            expected: "string",
+            found: "int16"
+        })
+    );
+}
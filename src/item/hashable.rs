@@ -0,0 +1,109 @@
+// `Item` can't be `Eq` or `Hash` because of the floats, this wrapper compares and hashes floats by
+// their bits instead. that makes NaN equal to itself, but 0.0 and -0.0 different
+
+use crate::Item;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+/// `Item` with bitwise float equality, so it can be used in sets and as a map key
+#[derive(Debug, Clone)]
+pub struct HashableItem(pub Item);
+
+impl PartialEq for HashableItem {
+    fn eq(&self, other: &Self) -> bool {
+        bits_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for HashableItem {}
+
+impl Hash for HashableItem {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_item(&self.0, state)
+    }
+}
+
+impl From<Item> for HashableItem {
+    fn from(item: Item) -> HashableItem {
+        HashableItem(item)
+    }
+}
+
+fn bits_eq(a: &Item, b: &Item) -> bool {
+    match (a, b) {
+        (Item::F32(a), Item::F32(b)) => a.to_bits() == b.to_bits(),
+        (Item::F64(a), Item::F64(b)) => a.to_bits() == b.to_bits(),
+        (Item::List(a), Item::List(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| bits_eq(a, b))
+        }
+        (Item::Map(a), Item::Map(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| bits_eq(a, b)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+fn hash_item<H: Hasher>(item: &Item, state: &mut H) {
+    core::mem::discriminant(item).hash(state);
+    match item {
+        Item::Map(map) => {
+            // the hash must not depend on the iteration order of the map
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by_key(|(key, _)| *key);
+            state.write_usize(entries.len());
+            for (key, item) in entries {
+                key.hash(state);
+                hash_item(item, state);
+            }
+        }
+        Item::Blob(data) => data.hash(state),
+        Item::CompressedBlob(blob) => blob.hash(state),
+        Item::List(list) => {
+            state.write_usize(list.len());
+            for item in list {
+                hash_item(item, state);
+            }
+        }
+        Item::Int16(n) => n.hash(state),
+        Item::Int64(n) => n.hash(state),
+        Item::F32(v) => v.to_bits().hash(state),
+        Item::F64(v) => v.to_bits().hash(state),
+        Item::String(text) => text.hash(state),
+        Item::Bool(v) => v.hash(state),
+        Item::Void => {}
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn hash_set_dedup_test() {
+    use std::collections::HashSet;
+
+    let records = vec![
+        Item::Int64(1),
+        Item::Int64(1),
+        Item::String(String::from("a")),
+        Item::map([(String::from("a"), Item::Int16(1))]),
+        Item::map([(String::from("a"), Item::Int16(1))]),
+        Item::F64(f64::NAN),
+        Item::F64(f64::NAN),
+    ];
+
+    let set: HashSet<HashableItem> = records.into_iter().map(HashableItem).collect();
+
+    assert_eq!(set.len(), 4);
+    assert!(set.contains(&HashableItem(Item::Int64(1))));
+    assert!(!set.contains(&HashableItem(Item::Int16(1))));
+}
+
+#[test]
+fn float_bits_eq_test() {
+    assert_eq!(
+        HashableItem(Item::F64(f64::NAN)),
+        HashableItem(Item::F64(f64::NAN))
+    );
+    assert_ne!(HashableItem(Item::F32(0.0)), HashableItem(Item::F32(-0.0)));
+    assert_ne!(HashableItem(Item::F32(1.0)), HashableItem(Item::F64(1.0)));
+}
@@ -0,0 +1,150 @@
+use crate::Item;
+use alloc::string::String;
+#[cfg(test)]
+use alloc::vec;
+use core::ops::Index;
+
+impl Item {
+    /// looks up a nested item by a json pointer (RFC 6901) like `/nested/list/2`. every part of
+    /// the path is a map key or a list index, `~1` in a key stands for `/` and `~0` for `~`. the
+    /// empty path is the item itself
+    pub fn pointer(&self, path: &str) -> Option<&Item> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        path.strip_prefix('/')?
+            .split('/')
+            .try_fold(self, |item, token| match item {
+                Item::Map(map) => map.get(unescape(token).as_str()),
+                Item::List(list) => list.get(parse_index(token)?),
+                _ => None,
+            })
+    }
+
+    /// like `pointer`, but the item can be changed
+    pub fn pointer_mut(&mut self, path: &str) -> Option<&mut Item> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        path.strip_prefix('/')?
+            .split('/')
+            .try_fold(self, |item, token| match item {
+                Item::Map(map) => map.get_mut(unescape(token).as_str()),
+                Item::List(list) => list.get_mut(parse_index(token)?),
+                _ => None,
+            })
+    }
+}
+
+fn unescape(token: &str) -> String {
+    // `~01` is `~1`, so `~1` has to be replaced first
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// list indices are plain digits without leading zeros
+fn parse_index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+/// `item["key"]` for maps.
+///
+/// panics if the item is not a map (that includes `Void` and every scalar) or the key is missing,
+/// use `Item::get` for a non panicking lookup
+impl Index<&str> for Item {
+    type Output = Item;
+
+    fn index(&self, key: &str) -> &Item {
+        match self {
+            Item::Map(map) => map
+                .get(key)
+                .unwrap_or_else(|| panic!("key {:?} not found in map", key)),
+            other => panic!("cannot index {} with key {:?}", other.type_name(), key),
+        }
+    }
+}
+
+/// `item[0]` for lists.
+///
+/// panics if the item is not a list or the index is out of bounds,
+/// use `Item::get_index` for a non panicking lookup
+impl Index<usize> for Item {
+    type Output = Item;
+
+    fn index(&self, index: usize) -> &Item {
+        match self {
+            Item::List(list) => list.get(index).unwrap_or_else(|| {
+                panic!(
+                    "index {} out of bounds for list of length {}",
+                    index,
+                    list.len()
+                )
+            }),
+            other => panic!("cannot index {} with index {}", other.type_name(), index),
+        }
+    }
+}
+
+#[test]
+fn index_test() {
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    let item = crate::from_slice(data).unwrap().unwrap();
+
+    assert_eq!(item["nested"]["list"][2], Item::Int64(123456789));
+    assert_eq!(
+        item["nested"]["data"],
+        Item::String(String::from("some text"))
+    );
+    assert_eq!(item["test"], Item::Int16(1));
+}
+
+#[test]
+#[should_panic(expected = "cannot index void with key \"test\"")]
+fn index_void_test() {
+    let _ = Item::Void["test"];
+}
+
+#[test]
+#[should_panic(expected = "index 1 out of bounds for list of length 1")]
+fn index_out_of_bounds_test() {
+    let _ = Item::List(vec![Item::Void])[1];
+}
+
+#[test]
+fn pointer_test() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    let item = crate::Parser::new(data.as_slice())
+        .parse()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(item.pointer("/nested/data"), Some(&Item::from("some text")));
+    assert_eq!(
+        item.pointer("/nested/list/2"),
+        Some(&Item::Int64(123456789))
+    );
+    assert_eq!(item.pointer(""), Some(&item));
+    assert_eq!(item.pointer("/nested/list/3"), None);
+    assert_eq!(item.pointer("/nested/list/02"), None);
+    assert_eq!(item.pointer("/test/0"), None);
+    assert_eq!(item.pointer("nested"), None);
+}
+
+#[test]
+fn pointer_escape_test() {
+    let mut item = Item::map([
+        (String::from("a/b"), Item::Int16(1)),
+        (String::from("m~n"), Item::Int16(2)),
+        (String::from(""), Item::Int16(3)),
+    ]);
+
+    assert_eq!(item.pointer("/a~1b"), Some(&Item::Int16(1)));
+    assert_eq!(item.pointer("/m~0n"), Some(&Item::Int16(2)));
+    assert_eq!(item.pointer("/"), Some(&Item::Int16(3)));
+
+    *item.pointer_mut("/a~1b").unwrap() = Item::Int16(4);
+    assert_eq!(item["a/b"], Item::Int16(4));
+}
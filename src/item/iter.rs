@@ -0,0 +1,102 @@
+// iterating an item goes over the elements of a list or the values of a map, anything else has
+// nothing to iterate
+
+use crate::{Item, Map};
+
+/// iterator over the children of an `&Item`, see `IntoIterator for &Item`
+pub enum Iter<'a> {
+    List(core::slice::Iter<'a, Item>),
+    Map(<&'a Map as IntoIterator>::IntoIter),
+    Empty,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Item;
+
+    fn next(&mut self) -> Option<&'a Item> {
+        match self {
+            Iter::List(list) => list.next(),
+            Iter::Map(map) => map.next().map(|(_, value)| value),
+            Iter::Empty => None,
+        }
+    }
+}
+
+/// the elements of a list or the values of a map in its iteration order, nothing for the other
+/// items
+impl<'a> IntoIterator for &'a Item {
+    type Item = &'a Item;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        match self {
+            Item::List(list) => Iter::List(list.iter()),
+            Item::Map(map) => Iter::Map(map.iter()),
+            _ => Iter::Empty,
+        }
+    }
+}
+
+/// iterator over the children of an `Item`, see `IntoIterator for Item`
+pub enum IntoIter {
+    List(alloc::vec::IntoIter<Item>),
+    Map(<Map as IntoIterator>::IntoIter),
+    Empty,
+}
+
+impl Iterator for IntoIter {
+    type Item = Item;
+
+    fn next(&mut self) -> Option<Item> {
+        match self {
+            IntoIter::List(list) => list.next(),
+            IntoIter::Map(map) => map.next().map(|(_, value)| value),
+            IntoIter::Empty => None,
+        }
+    }
+}
+
+/// like `IntoIterator for &Item` but takes the children, the keys of a map are dropped
+impl IntoIterator for Item {
+    type Item = Item;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        match self {
+            Item::List(list) => IntoIter::List(list.into_iter()),
+            Item::Map(map) => IntoIter::Map(map.into_iter()),
+            _ => IntoIter::Empty,
+        }
+    }
+}
+
+#[test]
+fn iter_list_test() {
+    let item = Item::list([Item::Int16(1), Item::Int64(2), Item::Int16(3)]);
+
+    let mut sum = 0;
+    for child in &item {
+        sum += child.as_integer().unwrap();
+    }
+    assert_eq!(sum, 6);
+
+    let owned: i64 = item
+        .into_iter()
+        .map(|child| child.as_integer().unwrap())
+        .sum();
+    assert_eq!(owned, 6);
+}
+
+#[test]
+fn iter_other_test() {
+    use alloc::string::String;
+
+    let item = Item::map([(String::from("a"), Item::Int16(4))]);
+    assert_eq!(
+        (&item).into_iter().collect::<alloc::vec::Vec<_>>(),
+        [&Item::Int16(4)]
+    );
+
+    assert_eq!(Item::Int16(1).into_iter().count(), 0);
+    assert_eq!((&Item::from("text")).into_iter().count(), 0);
+}
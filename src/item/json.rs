@@ -0,0 +1,120 @@
+// json has no binary type, so a blob becomes an object with the base64 of the data as its only
+// entry, under the `BLOB_KEY` key. converting back turns such objects into blobs again
+
+use crate::{Blob, Error, Item, Map};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{Number, Value};
+
+const BLOB_KEY: &str = "$base64";
+
+/// floats that json can't represent (NaN and the infinities) become `null`
+impl From<Item> for Value {
+    fn from(item: Item) -> Value {
+        match item {
+            Item::Void => Value::Null,
+            Item::Bool(v) => Value::Bool(v),
+            Item::Int16(n) => Value::from(n),
+            Item::Int64(n) => Value::from(n),
+            Item::F32(v) => Value::from(f64::from(v)),
+            Item::F64(v) => Value::from(v),
+            Item::String(text) => Value::String(text),
+            Item::Blob(data) | Item::CompressedBlob(Blob { data, .. }) => {
+                let mut object = serde_json::Map::new();
+                object.insert(String::from(BLOB_KEY), Value::String(STANDARD.encode(data)));
+                Value::Object(object)
+            }
+            Item::List(list) => Value::Array(list.into_iter().map(Value::from).collect()),
+            Item::Map(map) => Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, Value::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// integers become `Item::Int64`, other numbers `Item::F64`. fails on integers above `i64::MAX`
+/// and on blob objects that don't hold valid base64
+impl TryFrom<Value> for Item {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Item, Error> {
+        let item = match value {
+            Value::Null => Item::Void,
+            Value::Bool(v) => Item::Bool(v),
+            Value::Number(n) => number(n)?,
+            Value::String(text) => Item::String(text),
+            Value::Array(list) => Item::List(
+                list.into_iter()
+                    .map(Item::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(object) => match object.get(BLOB_KEY) {
+                Some(Value::String(text)) if object.len() == 1 => {
+                    let data = STANDARD
+                        .decode(text)
+                        .map_err(|e| Error::Json(format!("blob is not valid base64: {e}")))?;
+                    Item::Blob(data)
+                }
+                _ => Item::Map(
+                    object
+                        .into_iter()
+                        .map(|(key, value)| Ok((key, Item::try_from(value)?)))
+                        .collect::<Result<Map, Error>>()?,
+                ),
+            },
+        };
+        Ok(item)
+    }
+}
+
+fn number(n: Number) -> Result<Item, Error> {
+    if let Some(n) = n.as_i64() {
+        return Ok(Item::Int64(n));
+    }
+    if n.is_u64() {
+        return Err(Error::Json(format!("integer {n} does not fit in an int64")));
+    }
+    // without arbitrary precision every other number is a float
+    Ok(Item::F64(n.as_f64().unwrap_or(f64::NAN)))
+}
+
+#[test]
+fn json_round_trip_test() {
+    let item = Item::map([
+        (String::from("text"), Item::from("hello")),
+        (String::from("number"), Item::Int64(-12)),
+        (String::from("data"), Item::Blob(vec![0, 1, 2, 254, 255])),
+    ]);
+
+    let value = Value::from(item.clone());
+    assert_eq!(value["text"], "hello");
+    assert_eq!(value["number"], -12);
+    assert_eq!(value["data"][BLOB_KEY], "AAEC/v8=");
+
+    assert_eq!(Item::try_from(value).unwrap(), item);
+}
+
+#[test]
+fn json_scalars_test() {
+    assert_eq!(Value::from(Item::Void), Value::Null);
+    assert_eq!(Value::from(Item::Int16(3)), Value::from(3));
+    assert_eq!(Value::from(Item::F32(0.5)), Value::from(0.5));
+    assert_eq!(Value::from(Item::F64(f64::NAN)), Value::Null);
+
+    assert_eq!(Item::try_from(Value::from(0.25)).unwrap(), Item::F64(0.25));
+    assert_eq!(
+        Item::try_from(Value::from(u64::MAX)),
+        Err(Error::Json(String::from(
+            "integer 18446744073709551615 does not fit in an int64"
+        )))
+    );
+}
+
+#[test]
+fn json_invalid_blob_test() {
+    let value = serde_json::json!({ BLOB_KEY: "not base64!" });
+
+    assert!(matches!(Item::try_from(value), Err(Error::Json(_))));
+}
@@ -1,15 +1,142 @@
+use crate::{Blob, Error};
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+#[cfg(test)]
+use alloc::vec;
+use alloc::vec::Vec;
 use enum_as_inner::EnumAsInner;
+#[cfg(feature = "ordered-map")]
+use indexmap::IndexMap;
+#[cfg(all(feature = "std", not(feature = "ordered-map")))]
 use std::collections::HashMap;
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
+mod borrowed;
+mod convert;
+#[cfg(feature = "with-serde")]
+mod de;
+pub(crate) mod diff;
+pub(crate) mod display;
+mod from_item;
+mod hashable;
+mod index;
+mod iter;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "ndarray")]
+pub(crate) mod ndarray;
+mod ord;
+#[cfg(feature = "with-serde")]
+pub(crate) mod ser;
 #[cfg(feature = "with-serde")]
 mod serde_impl;
+pub(crate) mod size;
+
+pub use borrowed::{BorrowedItem, BorrowedMap};
+pub use from_item::FromItem;
+pub use hashable::HashableItem;
+pub use iter::{IntoIter, Iter};
+#[cfg(feature = "ndarray")]
+pub use ndarray::Dtype;
+#[cfg(feature = "with-serde")]
+pub use serde_impl::ItemSeed;
 
+#[cfg(all(feature = "std", not(feature = "ordered-map")))]
 pub type Map = HashMap<String, Item>;
+/// with the `ordered-map` feature keys keep the order they were inserted or parsed in
+#[cfg(feature = "ordered-map")]
+pub type Map = IndexMap<String, Item>;
+/// without std there is no hasher, so maps are ordered by key instead
+#[cfg(not(feature = "std"))]
+pub type Map = BTreeMap<String, Item>;
+
+#[cfg(all(feature = "std", not(feature = "ordered-map")))]
+pub(crate) fn map_with_capacity<K: core::hash::Hash + Eq, V>(capacity: usize) -> HashMap<K, V> {
+    HashMap::with_capacity(capacity)
+}
+
+#[cfg(feature = "ordered-map")]
+pub(crate) fn map_with_capacity<K: core::hash::Hash + Eq, V>(capacity: usize) -> IndexMap<K, V> {
+    IndexMap::with_capacity(capacity)
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn map_with_capacity<K: Ord, V>(_: usize) -> BTreeMap<K, V> {
+    BTreeMap::new()
+}
+
+/// maps `Parser::parse_into` can build the root map into, nested maps stay `Map`. implemented for
+/// `HashMap` and `BTreeMap`, and for `IndexMap` with the `ordered-map` feature
+pub trait MapLike: Default {
+    fn insert(&mut self, key: String, value: Item);
+
+    fn contains_key(&self, key: &str) -> bool;
+}
+
+#[cfg(feature = "std")]
+impl<S: core::hash::BuildHasher + Default> MapLike for std::collections::HashMap<String, Item, S> {
+    fn insert(&mut self, key: String, value: Item) {
+        std::collections::HashMap::insert(self, key, value);
+    }
 
-#[derive(Debug, PartialEq, EnumAsInner)]
+    fn contains_key(&self, key: &str) -> bool {
+        std::collections::HashMap::contains_key(self, key)
+    }
+}
+
+impl MapLike for alloc::collections::BTreeMap<String, Item> {
+    fn insert(&mut self, key: String, value: Item) {
+        alloc::collections::BTreeMap::insert(self, key, value);
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        alloc::collections::BTreeMap::contains_key(self, key)
+    }
+}
+
+#[cfg(feature = "ordered-map")]
+impl<S: core::hash::BuildHasher + Default> MapLike for IndexMap<String, Item, S> {
+    fn insert(&mut self, key: String, value: Item) {
+        IndexMap::insert(self, key, value);
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        IndexMap::contains_key(self, key)
+    }
+}
+
+/// a BSDF value.
+///
+/// equality follows the float semantics, so an item containing a NaN is never equal to anything,
+/// not even to itself. compare the `to_bits` of the floats to check for the exact value.
+///
+/// new variants can be added without a major release, so a `match` outside of this crate needs a
+/// wildcard arm:
+///
+/// ```
+/// use bsdf::Item;
+///
+/// fn describe(item: &Item) -> &'static str {
+///     match item {
+///         Item::Int16(_) | Item::Int64(_) => "integer",
+///         Item::String(_) => "string",
+///         _ => "something else",
+///     }
+/// }
+///
+/// assert_eq!(describe(&Item::Int16(1)), "integer");
+/// assert_eq!(describe(&Item::Void), "something else");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, EnumAsInner)]
+#[non_exhaustive]
 pub enum Item {
     Map(Map),
     Blob(Vec<u8>),
+    /// a blob that is written with its own compression instead of the one set on the `Encoder`,
+    /// the parser produces these with `Parser::keep_blob_compression`
+    CompressedBlob(Blob),
     List(Vec<Item>),
     Int16(i16),
     Int64(i64),
@@ -17,5 +144,591 @@ pub enum Item {
     F64(f64),
     String(String),
     Bool(bool),
+    /// the default, it holds no data so taking an item out with `mem::take` leaves nothing behind
+    #[default]
     Void,
 }
+
+impl Item {
+    /// shorthand for `Item::List(iter.into_iter().collect())`
+    pub fn list<I: IntoIterator<Item = Item>>(iter: I) -> Item {
+        iter.into_iter().collect()
+    }
+
+    /// shorthand for `Item::Map(iter.into_iter().collect())`
+    pub fn map<I: IntoIterator<Item = (String, Item)>>(iter: I) -> Item {
+        iter.into_iter().collect()
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Item::Map(_) => "map",
+            Item::Blob(_) | Item::CompressedBlob(_) => "blob",
+            Item::List(_) => "list",
+            Item::Int16(_) => "int16",
+            Item::Int64(_) => "int64",
+            Item::F32(_) => "f32",
+            Item::F64(_) => "f64",
+            Item::String(_) => "string",
+            Item::Bool(_) => "bool",
+            Item::Void => "void",
+        }
+    }
+
+    /// the value for `key` if this is a map
+    pub fn get(&self, key: &str) -> Option<&Item> {
+        self.as_map()?.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Item> {
+        self.as_map_mut()?.get_mut(key)
+    }
+
+    /// the element at `index` if this is a list
+    pub fn get_index(&self, index: usize) -> Option<&Item> {
+        self.as_list()?.get(index)
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut Item> {
+        self.as_list_mut()?.get_mut(index)
+    }
+
+    /// the number of entries of a map or list, or the number of bytes of a blob or string.
+    /// `None` for scalars and `Void`
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Item::Map(map) => Some(map.len()),
+            Item::List(list) => Some(list.len()),
+            Item::Blob(data) => Some(data.len()),
+            Item::CompressedBlob(blob) => Some(blob.data.len()),
+            Item::String(text) => Some(text.len()),
+            _ => None,
+        }
+    }
+
+    /// whether `len` is zero, `None` for scalars and `Void`
+    pub fn is_empty(&self) -> Option<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// like `into_map`, but fails with `Error::TypeMismatch` so it composes with `?`
+    pub fn try_into_map(self) -> Result<Map, Error> {
+        self.into_map()
+            .map_err(|other| Error::type_mismatch("map", &other))
+    }
+
+    /// like `into_list`, but fails with `Error::TypeMismatch` so it composes with `?`
+    pub fn try_into_list(self) -> Result<Vec<Item>, Error> {
+        self.into_list()
+            .map_err(|other| Error::type_mismatch("list", &other))
+    }
+
+    /// like `as_int16`, but by value and failing with `Error::TypeMismatch`
+    pub fn as_int16_or(&self) -> Result<i16, Error> {
+        self.as_int16()
+            .copied()
+            .ok_or_else(|| Error::type_mismatch("int16", self))
+    }
+
+    /// like `as_int64`, but by value and failing with `Error::TypeMismatch`
+    pub fn as_int64_or(&self) -> Result<i64, Error> {
+        self.as_int64()
+            .copied()
+            .ok_or_else(|| Error::type_mismatch("int64", self))
+    }
+
+    /// like `as_f32`, but by value and failing with `Error::TypeMismatch`
+    pub fn as_f32_or(&self) -> Result<f32, Error> {
+        self.as_f32()
+            .copied()
+            .ok_or_else(|| Error::type_mismatch("f32", self))
+    }
+
+    /// like `as_f64`, but by value and failing with `Error::TypeMismatch`
+    pub fn as_f64_or(&self) -> Result<f64, Error> {
+        self.as_f64()
+            .copied()
+            .ok_or_else(|| Error::type_mismatch("f64", self))
+    }
+
+    /// like `as_bool`, but by value and failing with `Error::TypeMismatch`
+    pub fn as_bool_or(&self) -> Result<bool, Error> {
+        self.as_bool()
+            .copied()
+            .ok_or_else(|| Error::type_mismatch("bool", self))
+    }
+
+    /// like `as_string`, but failing with `Error::TypeMismatch`
+    pub fn as_str_or(&self) -> Result<&str, Error> {
+        self.as_string()
+            .map(String::as_str)
+            .ok_or_else(|| Error::type_mismatch("string", self))
+    }
+
+    /// takes the item out and leaves `Void` in its place, `mem::take` for items
+    pub fn take(&mut self) -> Item {
+        core::mem::take(self)
+    }
+
+    /// puts `new` in place of this item and returns the old one
+    pub fn replace(&mut self, new: Item) -> Item {
+        core::mem::replace(self, new)
+    }
+
+    /// deep merges `other` into this item. when both are maps every entry of `other` is merged
+    /// into the entry with the same key, or added if there is none. anything else, lists too, is
+    /// replaced by `other`
+    pub fn merge(&mut self, other: Item) {
+        match (self, other) {
+            (Item::Map(map), Item::Map(other)) => {
+                for (key, value) in other {
+                    match map.get_mut(&key) {
+                        Some(entry) => entry.merge(value),
+                        None => {
+                            map.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (this, other) => *this = other,
+        }
+    }
+
+    /// calls `f` with this item and every item inside of it, depth first with parents before
+    /// their children. map values come in the iteration order of the map
+    pub fn walk(&self, f: &mut impl FnMut(&Item)) {
+        f(self);
+        match self {
+            Item::List(list) => list.iter().for_each(|item| item.walk(f)),
+            Item::Map(map) => map.values().for_each(|item| item.walk(f)),
+            _ => {}
+        }
+    }
+
+    /// like `walk`, but `f` can change the items. the children are visited after `f` ran on their
+    /// parent, so items that `f` puts in place are visited as well
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut Item)) {
+        f(self);
+        match self {
+            Item::List(list) => list.iter_mut().for_each(|item| item.walk_mut(f)),
+            Item::Map(map) => map.values_mut().for_each(|item| item.walk_mut(f)),
+            _ => {}
+        }
+    }
+
+    /// keeps the elements of a list for which `f` is true, like `Vec::retain`. only the direct
+    /// elements are looked at, anything but a list is left as it is
+    pub fn retain(&mut self, mut f: impl FnMut(&Item) -> bool) {
+        if let Item::List(list) = self {
+            list.retain(|item| f(item));
+        }
+    }
+
+    /// keeps the entries of a map for which `f` is true, like `HashMap::retain`. anything but a
+    /// map is left as it is
+    pub fn retain_entries(&mut self, mut f: impl FnMut(&str, &Item) -> bool) {
+        if let Item::Map(map) = self {
+            map.retain(|key, item| f(key, item));
+        }
+    }
+
+    /// the name of the extension the encoder marks this item with, array maps with the
+    /// `ndarray` feature
+    pub(crate) fn extension_name(&self) -> Option<&'static str> {
+        #[cfg(feature = "ndarray")]
+        if self.as_ndarray().is_some() {
+            return Some(ndarray::EXTENSION);
+        }
+        None
+    }
+
+    /// the number of items in the tree, this one included
+    pub fn count_nodes(&self) -> usize {
+        let mut count = 0;
+        self.walk(&mut |_| count += 1);
+        count
+    }
+
+    /// the approximate memory the tree takes: this item and the allocations of every string, blob,
+    /// list and map in it. maps count their entries because not every map type has a capacity
+    pub fn byte_size(&self) -> usize {
+        let mut size = core::mem::size_of::<Item>();
+        self.walk(&mut |item| {
+            size += match item {
+                Item::String(text) => text.capacity(),
+                Item::Blob(data) => data.capacity(),
+                Item::CompressedBlob(blob) => blob.data.capacity(),
+                Item::List(list) => list.capacity() * core::mem::size_of::<Item>(),
+                Item::Map(map) => map
+                    .keys()
+                    .map(|key| {
+                        key.capacity()
+                            + core::mem::size_of::<String>()
+                            + core::mem::size_of::<Item>()
+                    })
+                    .sum(),
+                _ => 0,
+            }
+        });
+        size
+    }
+
+    /// either integer type widened to `i64`, the width a value is stored with depends on the
+    /// writer so this is usually what you want
+    pub fn as_integer(&self) -> Result<i64, Error> {
+        match self {
+            Item::Int16(n) => Ok(i64::from(*n)),
+            Item::Int64(n) => Ok(*n),
+            other => Err(Error::type_mismatch("integer", other)),
+        }
+    }
+}
+
+#[test]
+fn type_name_test() {
+    assert_eq!(Item::Map(Map::new()).type_name(), "map");
+    assert_eq!(Item::Blob(vec![1]).type_name(), "blob");
+    assert_eq!(
+        Item::CompressedBlob(Blob::new(vec![1], crate::Compression::Zlib)).type_name(),
+        "blob"
+    );
+    assert_eq!(Item::List(vec![]).type_name(), "list");
+    assert_eq!(Item::Int16(1).type_name(), "int16");
+    assert_eq!(Item::Int64(1).type_name(), "int64");
+    assert_eq!(Item::F32(1.0).type_name(), "f32");
+    assert_eq!(Item::F64(1.0).type_name(), "f64");
+    assert_eq!(Item::String(String::new()).type_name(), "string");
+    assert_eq!(Item::Bool(true).type_name(), "bool");
+    assert_eq!(Item::Void.type_name(), "void");
+}
+
+#[test]
+fn len_test() {
+    let map = Item::map([
+        (String::from("a"), Item::Void),
+        (String::from("b"), Item::Void),
+    ]);
+    assert_eq!(map.len(), Some(2));
+    assert_eq!(map.is_empty(), Some(false));
+
+    assert_eq!(Item::List(vec![]).len(), Some(0));
+    assert_eq!(Item::List(vec![]).is_empty(), Some(true));
+
+    // bytes, not characters
+    assert_eq!(Item::from("é").len(), Some(2));
+    assert_eq!(Item::Blob(vec![1, 2, 3]).len(), Some(3));
+
+    assert_eq!(Item::Int64(5).len(), None);
+    assert_eq!(Item::Void.is_empty(), None);
+}
+
+#[test]
+fn constructor_test() {
+    let expected = Item::Map(Map::from_iter([
+        (String::from("test"), Item::Int16(1)),
+        (String::from("test1"), Item::Int16(2)),
+        (String::from("test3"), Item::Int16(4)),
+    ]));
+
+    let item = Item::map([
+        (String::from("test"), Item::Int16(1)),
+        (String::from("test1"), Item::Int16(2)),
+        (String::from("test3"), Item::Int16(4)),
+    ]);
+    assert_eq!(item, expected);
+
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+    assert_eq!(crate::from_slice(data).unwrap(), Some(item));
+
+    assert_eq!(
+        Item::list([Item::Bool(true), Item::Void]),
+        Item::List(vec![Item::Bool(true), Item::Void])
+    );
+    assert_eq!(
+        (1..3).map(Item::Int64).collect::<Item>(),
+        Item::List(vec![Item::Int64(1), Item::Int64(2)])
+    );
+}
+
+#[test]
+fn clone_test() {
+    let original = Item::map([(
+        String::from("nested"),
+        Item::map([(String::from("list"), Item::list([Item::Int16(1)]))]),
+    )]);
+
+    let mut copy = original.clone();
+    if let Some(list) = copy
+        .get_mut("nested")
+        .and_then(|x| x.get_mut("list"))
+        .and_then(Item::as_list_mut)
+    {
+        list.push(Item::Int16(2));
+    }
+
+    assert_eq!(original["nested"]["list"], Item::list([Item::Int16(1)]));
+    assert_eq!(
+        copy["nested"]["list"],
+        Item::list([Item::Int16(1), Item::Int16(2)])
+    );
+}
+
+#[test]
+fn get_test() {
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    let item = crate::from_slice(data).unwrap().unwrap();
+
+    let value = item
+        .get("nested")
+        .and_then(|x| x.get("list"))
+        .and_then(|x| x.get_index(2));
+
+    assert_eq!(value, Some(&Item::Int64(123456789)));
+    assert_eq!(item.get("missing"), None);
+    assert_eq!(item.get_index(0), None);
+    assert_eq!(Item::Void.get("test"), None);
+}
+
+#[test]
+fn get_mut_test() {
+    let mut item = Item::Map(Map::from_iter([(
+        String::from("list"),
+        Item::List(vec![Item::Bool(false)]),
+    )]));
+
+    if let Some(x) = item.get_mut("list").and_then(|x| x.get_index_mut(0)) {
+        *x = Item::Bool(true)
+    }
+
+    assert_eq!(
+        item.get("list").and_then(|x| x.get_index(0)),
+        Some(&Item::Bool(true))
+    );
+}
+
+#[test]
+fn try_into_map_test() {
+    let map = || Map::from_iter([(String::from("a"), Item::Int16(1))]);
+
+    assert_eq!(Item::Map(map()).try_into_map(), Ok(map()));
+    assert_eq!(
+        Item::List(vec![]).try_into_map(),
+        Err(Error::TypeMismatch {
+            expected: "map",
+            found: "list"
+        })
+    );
+}
+
+#[test]
+fn try_into_list_test() {
+    assert_eq!(
+        Item::List(vec![Item::Bool(true)]).try_into_list(),
+        Ok(vec![Item::Bool(true)])
+    );
+    assert_eq!(
+        Item::Int64(5).try_into_list(),
+        Err(Error::TypeMismatch {
+            expected: "list",
+            found: "int64"
+        })
+    );
+}
+
+#[test]
+fn as_or_test() {
+    assert_eq!(Item::Int64(5).as_int64_or(), Ok(5));
+    assert_eq!(Item::Bool(true).as_bool_or(), Ok(true));
+    assert_eq!(Item::from("text").as_str_or(), Ok("text"));
+    assert_eq!(
+        Item::Int16(5).as_int64_or(),
+        Err(Error::TypeMismatch {
+            expected: "int64",
+            found: "int16"
+        })
+    );
+    assert_eq!(
+        Item::Void.as_f64_or(),
+        Err(Error::TypeMismatch {
+            expected: "f64",
+            found: "void"
+        })
+    );
+}
+
+#[test]
+fn as_integer_test() {
+    assert_eq!(Item::Int16(-3).as_integer(), Ok(-3));
+    assert_eq!(Item::Int16(i16::MAX).as_integer(), Ok(i64::from(i16::MAX)));
+    assert_eq!(Item::Int64(i64::MIN).as_integer(), Ok(i64::MIN));
+    assert_eq!(
+        Item::F32(1.0).as_integer(),
+        Err(Error::TypeMismatch {
+            expected: "integer",
+            found: "f32"
+        })
+    );
+}
+
+#[test]
+fn walk_test() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    let item = crate::Parser::new(data.as_slice())
+        .parse()
+        .unwrap()
+        .unwrap();
+
+    let mut integers = Vec::new();
+    let mut nodes = 0;
+    item.walk(&mut |item| {
+        nodes += 1;
+        if let Ok(n) = item.as_integer() {
+            integers.push(n);
+        }
+    });
+    integers.sort_unstable();
+
+    assert_eq!(integers, vec![-1, 1, 123456789]);
+    assert_eq!(nodes, 9);
+}
+
+#[test]
+fn retain_test() {
+    let mut item = Item::list([
+        Item::Bool(false),
+        Item::Int16(1),
+        Item::Bool(true),
+        Item::Bool(false),
+        Item::list([Item::Bool(false)]),
+    ]);
+    item.retain(|item| *item != Item::Bool(false));
+    assert_eq!(
+        item,
+        Item::list([
+            Item::Int16(1),
+            Item::Bool(true),
+            Item::list([Item::Bool(false)])
+        ])
+    );
+
+    let mut item = Item::map([
+        (String::from("data"), Item::Blob(vec![0; 1024])),
+        (String::from("_internal"), Item::Void),
+        (String::from("name"), Item::from("text")),
+    ]);
+    item.retain_entries(|key, item| !key.starts_with('_') && item.as_blob().is_none());
+    assert_eq!(
+        item,
+        Item::map([(String::from("name"), Item::from("text"))])
+    );
+
+    let mut item = Item::Int16(1);
+    item.retain(|_| false);
+    item.retain_entries(|_, _| false);
+    assert_eq!(item, Item::Int16(1));
+}
+
+#[test]
+fn metrics_test() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    let item = crate::Parser::new(data.as_slice())
+        .parse()
+        .unwrap()
+        .unwrap();
+
+    // the root map, `test`, `nested`, `nested.nested`, `nested.list` with its three items and
+    // `nested.data`
+    assert_eq!(item.count_nodes(), 9);
+    assert_eq!(Item::Void.count_nodes(), 1);
+
+    let item_size = core::mem::size_of::<Item>();
+    assert_eq!(Item::Void.byte_size(), item_size);
+    let mut text = String::with_capacity(32);
+    text.push_str("text");
+    assert_eq!(Item::String(text).byte_size(), item_size + 32);
+    assert_eq!(
+        Item::List(vec![Item::Blob(vec![0; 10])]).byte_size(),
+        2 * item_size + 10
+    );
+}
+
+#[test]
+fn walk_mut_test() {
+    let mut item = Item::map([
+        (String::from("a"), Item::from("text")),
+        (
+            String::from("b"),
+            Item::list([Item::from("more"), Item::Int16(1)]),
+        ),
+    ]);
+
+    item.walk_mut(&mut |item| {
+        if let Item::String(text) = item {
+            *text = text.to_uppercase();
+        }
+    });
+
+    assert_eq!(item["a"], Item::from("TEXT"));
+    assert_eq!(item["b"][0], Item::from("MORE"));
+    assert_eq!(item["b"][1], Item::Int16(1));
+}
+
+#[test]
+fn default_test() {
+    assert_eq!(Item::default(), Item::Void);
+
+    let mut item = Item::list([Item::from("text")]);
+    let taken = core::mem::take(item.get_index_mut(0).unwrap());
+    assert_eq!(taken, Item::from("text"));
+    assert_eq!(item, Item::list([Item::Void]));
+}
+
+#[test]
+fn merge_test() {
+    // copied from python
+    let base = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    let mut item = crate::Parser::new(base.as_slice())
+        .parse()
+        .unwrap()
+        .unwrap();
+
+    let overrides = Item::map([
+        (String::from("extra"), Item::Bool(false)),
+        (
+            String::from("nested"),
+            Item::map([
+                (String::from("data"), Item::from("other text")),
+                (String::from("list"), Item::list([Item::Int16(7)])),
+            ]),
+        ),
+    ]);
+    item.merge(overrides);
+
+    assert_eq!(item["test"], Item::Int16(1));
+    assert_eq!(item["extra"], Item::Bool(false));
+    assert_eq!(item["nested"]["nested"], Item::Bool(true));
+    assert_eq!(item["nested"]["data"], Item::from("other text"));
+    assert_eq!(item["nested"]["list"], Item::list([Item::Int16(7)]));
+
+    // a map replaces a scalar and the other way around
+    item.merge(Item::map([(String::from("nested"), Item::Void)]));
+    assert_eq!(item["nested"], Item::Void);
+}
+
+#[test]
+fn take_replace_test() {
+    let mut item = Item::map([(
+        String::from("nested"),
+        Item::map([(String::from("data"), Item::from("text"))]),
+    )]);
+
+    let slot = item.pointer_mut("/nested/data").unwrap();
+    assert_eq!(slot.take(), Item::from("text"));
+    assert_eq!(item["nested"]["data"], Item::Void);
+
+    let slot = item.pointer_mut("/nested/data").unwrap();
+    assert_eq!(slot.replace(Item::Int16(1)), Item::Void);
+    assert_eq!(item["nested"]["data"], Item::Int16(1));
+}
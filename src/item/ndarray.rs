@@ -0,0 +1,219 @@
+// numpy arrays in the form of the `ndarray` extension of the reference implementation: a map with
+// the `shape` as a list of integers, the `dtype` by its numpy name and the raw little endian
+// elements as a `data` blob. the map is marked with the extension name, the parser reads that
+// marker and the encoder writes it for every map that `as_ndarray` recognizes
+
+use crate::{Blob, Item};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// the name the reference implementation marks array maps with
+pub(crate) const EXTENSION: &str = "ndarray";
+
+/// the element type of an array, named like numpy does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dtype {
+    Bool,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    Float32,
+    Float64,
+}
+
+impl Dtype {
+    /// the numpy name, as stored in the `dtype` entry
+    pub fn name(self) -> &'static str {
+        match self {
+            Dtype::Bool => "bool",
+            Dtype::Int8 => "int8",
+            Dtype::Int16 => "int16",
+            Dtype::Int32 => "int32",
+            Dtype::Int64 => "int64",
+            Dtype::UInt8 => "uint8",
+            Dtype::UInt16 => "uint16",
+            Dtype::UInt32 => "uint32",
+            Dtype::UInt64 => "uint64",
+            Dtype::Float32 => "float32",
+            Dtype::Float64 => "float64",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Dtype> {
+        let dtype = match name {
+            "bool" => Dtype::Bool,
+            "int8" => Dtype::Int8,
+            "int16" => Dtype::Int16,
+            "int32" => Dtype::Int32,
+            "int64" => Dtype::Int64,
+            "uint8" => Dtype::UInt8,
+            "uint16" => Dtype::UInt16,
+            "uint32" => Dtype::UInt32,
+            "uint64" => Dtype::UInt64,
+            "float32" => Dtype::Float32,
+            "float64" => Dtype::Float64,
+            _ => return None,
+        };
+        Some(dtype)
+    }
+
+    /// the bytes of one element
+    pub fn size(self) -> usize {
+        match self {
+            Dtype::Bool | Dtype::Int8 | Dtype::UInt8 => 1,
+            Dtype::Int16 | Dtype::UInt16 => 2,
+            Dtype::Int32 | Dtype::UInt32 | Dtype::Float32 => 4,
+            Dtype::Int64 | Dtype::UInt64 | Dtype::Float64 => 8,
+        }
+    }
+}
+
+impl Item {
+    /// the array map for `data`, the little endian bytes of the elements in row major order
+    pub fn ndarray(shape: &[usize], dtype: Dtype, data: Vec<u8>) -> Item {
+        let shape = shape.iter().map(|n| Item::Int64(*n as i64));
+        Item::map([
+            (String::from("shape"), Item::list(shape)),
+            (String::from("dtype"), Item::from(dtype.name())),
+            (String::from("data"), Item::Blob(data)),
+        ])
+    }
+
+    /// the shape, element type and data of an array map. `None` if this is not a map in that
+    /// form, or if the size of the data does not match the shape
+    pub fn as_ndarray(&self) -> Option<(Vec<usize>, Dtype, &[u8])> {
+        let shape = self
+            .get("shape")?
+            .as_list()?
+            .iter()
+            .map(|n| usize::try_from(n.as_integer().ok()?).ok())
+            .collect::<Option<Vec<usize>>>()?;
+        let dtype = Dtype::from_name(self.get("dtype")?.as_string()?)?;
+        let data = match self.get("data")? {
+            Item::Blob(data) => data.as_slice(),
+            Item::CompressedBlob(Blob { data, .. }) => data.as_slice(),
+            _ => return None,
+        };
+
+        let elements = shape
+            .iter()
+            .try_fold(1usize, |total, n| total.checked_mul(*n))?;
+        if elements.checked_mul(dtype.size())? != data.len() {
+            return None;
+        }
+        Some((shape, dtype, data))
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn ndarray_round_trip_test() {
+    let values = [1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0];
+    let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+
+    let item = Item::ndarray(&[2, 3], Dtype::Float64, data.clone());
+    let mut buffer = Vec::new();
+    crate::Encoder::new(&mut buffer).write(&item).unwrap();
+    let parsed = crate::Parser::new(buffer.as_slice())
+        .parse()
+        .unwrap()
+        .unwrap();
+
+    let (shape, dtype, bytes) = parsed.as_ndarray().unwrap();
+    assert_eq!(shape, vec![2, 3]);
+    assert_eq!(dtype, Dtype::Float64);
+    assert_eq!(bytes, data.as_slice());
+    assert_eq!(f64::from_le_bytes(bytes[40..].try_into().unwrap()), 6.0);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn ndarray_root_test() {
+    use crate::Error;
+    use alloc::collections::BTreeMap;
+
+    // the root map is marked as an ndarray, the root value readers look past the extension
+    let item = Item::ndarray(&[2], Dtype::UInt8, vec![1, 2]);
+    let data = crate::to_vec(&item).unwrap();
+    let entries: BTreeMap<String, Item> = item.as_map().unwrap().clone().into_iter().collect();
+
+    let mut parser = crate::Parser::new(data.as_slice());
+    assert_eq!(parser.peek_type(), Ok(Some("map")));
+    assert_eq!(parser.parse(), Ok(Some(item.clone())));
+
+    let parsed = crate::Parser::new(data.as_slice())
+        .parse_into::<BTreeMap<String, Item>>()
+        .unwrap();
+    assert_eq!(parsed, Some(entries.clone()));
+
+    let iterated = crate::Parser::new(data.as_slice())
+        .into_map_iter()
+        .unwrap()
+        .collect::<Result<BTreeMap<_, _>, Error>>()
+        .unwrap();
+    assert_eq!(iterated, entries);
+
+    assert_eq!(
+        crate::Parser::new(data.as_slice())
+            .into_list_iter()
+            .map(|_| ())
+            .map_err(Error::into_inner),
+        Err(Error::TypeMismatch {
+            expected: "list",
+            found: "map"
+        })
+    );
+}
+
+#[test]
+fn ndarray_reference_test() {
+    // `bsdf.encode(numpy.array([[1, 2, 3], [4, 5, 6]], "float64"))` with the reference
+    // implementation: the map is marked by `M` and the extension name, the shape is written as
+    // int16 and the data blob is aligned to 8 bytes
+    let mut data = b"BSDF\x02\x02M\x07ndarray\x03\x05shapel\x02h\x02\x00h\x03\x00\x05dtypes\x07float64\x04datab000\x00\x00\x07\x00\x00\x00\x00\x00\x00\x00".to_vec();
+    let values = [1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0];
+    data.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+
+    let parsed = crate::Parser::new(data.as_slice())
+        .parse()
+        .unwrap()
+        .unwrap();
+    let (shape, dtype, bytes) = parsed.as_ndarray().unwrap();
+    assert_eq!(shape, vec![2, 3]);
+    assert_eq!(dtype, Dtype::Float64);
+    assert_eq!(f64::from_le_bytes(bytes[40..].try_into().unwrap()), 6.0);
+
+    #[cfg(feature = "std")]
+    {
+        // the shape was int16 in the reference bytes
+        let item = Item::map([
+            (
+                String::from("shape"),
+                Item::list([Item::Int16(2), Item::Int16(3)]),
+            ),
+            (String::from("dtype"), Item::from("float64")),
+            (String::from("data"), Item::Blob(bytes.to_vec())),
+        ]);
+        let written = crate::to_vec(&item).unwrap();
+        assert_eq!(&written[..15], b"BSDF\x02\x02M\x07ndarray");
+        assert_eq!(crate::encoded_size(&item), written.len());
+    }
+}
+
+#[test]
+fn ndarray_invalid_test() {
+    assert_eq!(Item::Int16(1).as_ndarray(), None);
+    // six elements of eight bytes don't fit in four bytes
+    assert_eq!(
+        Item::ndarray(&[2, 3], Dtype::Float64, vec![0; 4]).as_ndarray(),
+        None
+    );
+    let mut item = Item::ndarray(&[2], Dtype::UInt8, vec![0; 2]);
+    *item.get_mut("dtype").unwrap() = Item::from("complex128");
+    assert_eq!(item.as_ndarray(), None);
+}
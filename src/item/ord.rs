@@ -0,0 +1,115 @@
+use crate::Item;
+#[cfg(test)]
+use alloc::vec;
+use core::cmp::Ordering;
+
+impl Item {
+    /// equality by value instead of by variant: `Int16` and `Int64` are equal when the value is,
+    /// the same goes for `F32` and `F64`, and blobs compare their data whatever their compression.
+    /// everything else, also inside lists and maps, compares like `==`
+    pub fn value_eq(&self, other: &Item) -> bool {
+        match (self, other) {
+            (Item::Int16(a), Item::Int64(b)) | (Item::Int64(b), Item::Int16(a)) => {
+                i64::from(*a) == *b
+            }
+            (Item::F32(a), Item::F64(b)) | (Item::F64(b), Item::F32(a)) => f64::from(*a) == *b,
+            (Item::Blob(a), Item::CompressedBlob(b)) | (Item::CompressedBlob(b), Item::Blob(a)) => {
+                *a == b.data
+            }
+            (Item::CompressedBlob(a), Item::CompressedBlob(b)) => a.data == b.data,
+            (Item::List(a), Item::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.value_eq(b))
+            }
+            (Item::Map(a), Item::Map(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).is_some_and(|b| a.value_eq(b)))
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// orders items of the same kind: integers with integers, floats with floats, and strings, bools,
+/// blobs and lists among themselves. `Int16` and `Int64` compare by value, an equal value puts the
+/// `Int16` first so the order agrees with `==`, the same goes for `F32` and `F64`.
+///
+/// different kinds, NaN and maps that are not equal have no order
+impl PartialOrd for Item {
+    fn partial_cmp(&self, other: &Item) -> Option<Ordering> {
+        match (self, other) {
+            (Item::Void, Item::Void) => Some(Ordering::Equal),
+            (Item::Bool(a), Item::Bool(b)) => a.partial_cmp(b),
+            (Item::Int16(a), Item::Int16(b)) => a.partial_cmp(b),
+            (Item::Int64(a), Item::Int64(b)) => a.partial_cmp(b),
+            (Item::Int16(a), Item::Int64(b)) => Some(i64::from(*a).cmp(b).then(Ordering::Less)),
+            (Item::Int64(a), Item::Int16(b)) => Some(a.cmp(&i64::from(*b)).then(Ordering::Greater)),
+            (Item::F32(a), Item::F32(b)) => a.partial_cmp(b),
+            (Item::F64(a), Item::F64(b)) => a.partial_cmp(b),
+            (Item::F32(a), Item::F64(b)) => f64::from(*a)
+                .partial_cmp(b)
+                .map(|order| order.then(Ordering::Less)),
+            (Item::F64(a), Item::F32(b)) => a
+                .partial_cmp(&f64::from(*b))
+                .map(|order| order.then(Ordering::Greater)),
+            (Item::String(a), Item::String(b)) => a.partial_cmp(b),
+            (Item::Blob(a), Item::Blob(b)) => a.partial_cmp(b),
+            (Item::CompressedBlob(a), Item::CompressedBlob(b))
+                if a.compression == b.compression =>
+            {
+                a.data.partial_cmp(&b.data)
+            }
+            (Item::List(a), Item::List(b)) => a.partial_cmp(b),
+            (Item::Map(a), Item::Map(b)) if a == b => Some(Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn sorts_integers_test() {
+    let mut list = vec![
+        Item::Int64(300),
+        Item::Int16(-2),
+        Item::Int64(-70000),
+        Item::Int16(5),
+        Item::Int64(5),
+    ];
+
+    list.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    assert_eq!(
+        list,
+        vec![
+            Item::Int64(-70000),
+            Item::Int16(-2),
+            Item::Int16(5),
+            Item::Int64(5),
+            Item::Int64(300),
+        ]
+    );
+}
+
+#[test]
+fn partial_cmp_test() {
+    assert!(Item::F32(1.5) < Item::F64(2.0));
+    assert!(Item::from("a") < Item::from("b"));
+    assert!(Item::Bool(false) < Item::Bool(true));
+    assert!(Item::list([Item::Int16(1)]) < Item::list([Item::Int16(2)]));
+
+    assert_eq!(Item::Int16(1).partial_cmp(&Item::F64(1.0)), None);
+    assert_eq!(Item::from("1").partial_cmp(&Item::Int16(1)), None);
+    assert_eq!(Item::F64(f64::NAN).partial_cmp(&Item::F64(1.0)), None);
+}
+
+#[test]
+fn value_eq_test() {
+    assert!(Item::Int16(5).value_eq(&Item::Int64(5)));
+    assert!(Item::Int64(5).value_eq(&Item::Int16(5)));
+    assert!(!Item::Int16(5).value_eq(&Item::Int64(6)));
+    assert!(Item::F32(1.5).value_eq(&Item::F64(1.5)));
+    assert!(!Item::Int16(1).value_eq(&Item::F64(1.0)));
+    assert!(Item::list([Item::Int16(1), Item::F32(0.5)])
+        .value_eq(&Item::list([Item::Int64(1), Item::F64(0.5)])));
+    assert!(!Item::F64(f64::NAN).value_eq(&Item::F64(f64::NAN)));
+}
@@ -0,0 +1,483 @@
+// serializer that builds an `Item` tree out of any `Serialize` type, the mapping matches the
+// `Serialize` impl of `Item` itself. enum variants with data become a map with a single entry
+
+use crate::{Error, Item, Map};
+use serde::ser::{self, Impossible, Serialize};
+
+impl ser::Error for Error {
+    fn custom<T: core::fmt::Display>(msg: T) -> Error {
+        Error::Serde(msg.to_string())
+    }
+}
+
+pub(crate) struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Item;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeVariant<SerializeList>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeVariant<SerializeMap>;
+
+    fn serialize_bool(self, v: bool) -> Result<Item, Error> {
+        Ok(Item::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Item, Error> {
+        Ok(Item::Int16(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Item, Error> {
+        Ok(Item::Int16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Item, Error> {
+        Ok(Item::Int64(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Item, Error> {
+        Ok(Item::Int64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Item, Error> {
+        Ok(Item::Int16(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Item, Error> {
+        Ok(Item::Int64(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Item, Error> {
+        Ok(Item::Int64(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Item, Error> {
+        i64::try_from(v)
+            .map(Item::Int64)
+            .map_err(|_| Error::Serde(format!("integer {v} does not fit in an int64")))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Item, Error> {
+        Ok(Item::F32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Item, Error> {
+        Ok(Item::F64(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Item, Error> {
+        Ok(Item::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Item, Error> {
+        Ok(Item::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Item, Error> {
+        Ok(Item::Blob(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Item, Error> {
+        Ok(Item::Void)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Item, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Item, Error> {
+        Ok(Item::Void)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Item, Error> {
+        Ok(Item::Void)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Item, Error> {
+        Ok(Item::from(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Item, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Item, Error> {
+        let value = value.serialize(self)?;
+        Ok(Item::map([(variant.to_string(), value)]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, Error> {
+        Ok(SerializeList(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeList>, Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeMap, Error> {
+        Ok(SerializeMap {
+            map: Map::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeMap>, Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+pub(crate) struct SerializeList(Vec<Item>);
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Item;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.0.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Item, Error> {
+        Ok(Item::List(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Item;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Item, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Item;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Item, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub(crate) struct SerializeMap {
+    map: Map,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Item;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::Serde(String::from("map value without a key")))?;
+        self.map.insert(key, value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Item, Error> {
+        Ok(Item::Map(self.map))
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Item;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.map
+            .insert(key.to_string(), value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Item, Error> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// wraps the data of a variant in a map with the variant name as the only key
+pub(crate) struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl SerializeVariant<SerializeList> {
+    fn wrap(self) -> Result<Item, Error> {
+        let value = ser::SerializeSeq::end(self.inner)?;
+        Ok(Item::map([(self.variant.to_string(), value)]))
+    }
+}
+
+impl SerializeVariant<SerializeMap> {
+    fn wrap(self) -> Result<Item, Error> {
+        let value = ser::SerializeMap::end(self.inner)?;
+        Ok(Item::map([(self.variant.to_string(), value)]))
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeList> {
+    type Ok = Item;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Item, Error> {
+        self.wrap()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeMap> {
+    type Ok = Item;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Item, Error> {
+        self.wrap()
+    }
+}
+
+/// map keys are strings in BSDF, so only string like keys are accepted
+pub(crate) struct KeySerializer;
+
+fn key_must_be_a_string() -> Error {
+    Error::Serde(String::from("map key must be a string"))
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    fn serialize_str(self, v: &str) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<String, Error> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _: bool) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i8(self, _: i8) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i16(self, _: i16) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i32(self, _: i32) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u8(self, _: u8) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u16(self, _: u16) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u32(self, _: u32) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _: &T) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<String, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(key_must_be_a_string())
+    }
+}
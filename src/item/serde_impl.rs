@@ -1,8 +1,9 @@
-/// derived serde method doesn't handle the blob variant correctly, so we implement it by hand
+// derived serde method doesn't handle the blob variant correctly, so we implement it by hand
 
 use crate::Item;
 use crate::Map;
-use serde::de::{self, SeqAccess};
+use core::cell::Cell;
+use serde::de::{self, DeserializeSeed, SeqAccess};
 use serde::ser::{self, SerializeMap, SerializeSeq};
 use serde::Serialize;
 use serde::{Deserialize, Deserializer};
@@ -15,142 +16,222 @@ impl<'de> Deserialize<'de> for Item {
     where
         D: Deserializer<'de>,
     {
-        struct ItemVisitor;
+        ItemSeed::new().deserialize(de)
+    }
+}
 
-        impl<'de> serde::de::Visitor<'de> for ItemVisitor {
-            type Value = Item;
+/// deserializes an `Item` with options, `Item::deserialize` is this with the defaults.
+///
+/// serde formats without a bytes type hand over a `Vec<u8>` as a sequence of `u8`, which
+/// becomes a list of `Int16` unless `u8_seq_as_blob` is set
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ItemSeed {
+    u8_seq_as_blob: bool,
+}
 
-            #[cold]
-            fn expecting(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
-                "any valid BSDF value".fmt(fmt)
-            }
+impl ItemSeed {
+    pub fn new() -> ItemSeed {
+        ItemSeed::default()
+    }
 
-            #[inline]
-            fn visit_some<D>(self, de: D) -> Result<Item, D::Error>
-            where
-                D: Deserializer<'de>,
-            {
-                Deserialize::deserialize(de)
-            }
+    /// turn a sequence of only `u8` values into `Item::Blob`, an empty sequence stays a list.
+    /// this is decided by the serde types, an `i16` of the same value is not a byte
+    pub fn u8_seq_as_blob(mut self, enabled: bool) -> Self {
+        self.u8_seq_as_blob = enabled;
+        self
+    }
+}
 
-            #[inline]
-            fn visit_none<E>(self) -> Result<Item, E> {
-                Ok(Item::Void)
-            }
+impl<'de> DeserializeSeed<'de> for ItemSeed {
+    type Value = Item;
 
-            #[inline]
-            fn visit_unit<E>(self) -> Result<Item, E> {
-                Ok(Item::Void)
-            }
+    fn deserialize<D>(self, de: D) -> Result<Item, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de.deserialize_any(ItemVisitor {
+            u8_seq_as_blob: self.u8_seq_as_blob,
+            u8_count: None,
+        })
+    }
+}
 
-            #[inline]
-            fn visit_bool<E>(self, value: bool) -> Result<Item, E> {
-                Ok(Item::Bool(value))
-            }
+/// an element of a sequence, counts whether it was a `u8`
+struct Element<'a> {
+    u8_seq_as_blob: bool,
+    u8_count: &'a Cell<usize>,
+}
 
-            #[inline]
-            fn visit_i64<E>(self, value: i64) -> Result<Item, E> {
-                Ok(Item::Int64(value))
-            }
+impl<'de> DeserializeSeed<'de> for Element<'_> {
+    type Value = Item;
 
-            #[inline]
-            fn visit_i32<E>(self, value: i32) -> Result<Item, E> {
-                Ok(Item::Int64(value.into()))
-            }
+    fn deserialize<D>(self, de: D) -> Result<Item, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de.deserialize_any(ItemVisitor {
+            u8_seq_as_blob: self.u8_seq_as_blob,
+            u8_count: Some(self.u8_count),
+        })
+    }
+}
 
-            #[inline]
-            fn visit_i16<E>(self, value: i16) -> Result<Item, E> {
-                Ok(Item::Int16(value))
-            }
+struct ItemVisitor<'a> {
+    u8_seq_as_blob: bool,
+    // set for the elements of a sequence
+    u8_count: Option<&'a Cell<usize>>,
+}
 
-            #[inline]
-            fn visit_i8<E>(self, value: i8) -> Result<Item, E> {
-                Ok(Item::Int16(value.into()))
-            }
+impl<'de> serde::de::Visitor<'de> for ItemVisitor<'_> {
+    type Value = Item;
 
-            #[inline]
-            fn visit_u32<E>(self, value: u32) -> Result<Item, E> {
-                Ok(Item::Int64(value.into()))
-            }
+    #[cold]
+    fn expecting(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        "any valid BSDF value".fmt(fmt)
+    }
 
-            #[inline]
-            fn visit_u16<E>(self, value: u16) -> Result<Item, E> {
-                Ok(Item::Int64(value.into()))
-            }
+    #[inline]
+    fn visit_some<D>(self, de: D) -> Result<Item, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        ItemSeed::new()
+            .u8_seq_as_blob(self.u8_seq_as_blob)
+            .deserialize(de)
+    }
 
-            #[inline]
-            fn visit_u8<E>(self, value: u8) -> Result<Item, E> {
-                Ok(Item::Int16(value.into()))
-            }
+    #[inline]
+    fn visit_none<E>(self) -> Result<Item, E> {
+        Ok(Item::Void)
+    }
 
-            #[inline]
-            fn visit_f32<E>(self, value: f32) -> Result<Item, E> {
-                Ok(Item::F32(value))
-            }
+    #[inline]
+    fn visit_unit<E>(self) -> Result<Item, E> {
+        Ok(Item::Void)
+    }
 
-            #[inline]
-            fn visit_f64<E>(self, value: f64) -> Result<Item, E> {
-                Ok(Item::F64(value))
-            }
+    #[inline]
+    fn visit_bool<E>(self, value: bool) -> Result<Item, E> {
+        Ok(Item::Bool(value))
+    }
 
-            #[inline]
-            fn visit_string<E>(self, value: String) -> Result<Item, E> {
-                Ok(Item::String(String::from(value)))
-            }
+    #[inline]
+    fn visit_i64<E>(self, value: i64) -> Result<Item, E> {
+        Ok(Item::Int64(value))
+    }
 
-            #[inline]
-            fn visit_str<E>(self, value: &str) -> Result<Item, E>
-            where
-                E: de::Error,
-            {
-                self.visit_string(String::from(value))
-            }
+    #[inline]
+    fn visit_i32<E>(self, value: i32) -> Result<Item, E> {
+        Ok(Item::Int64(value.into()))
+    }
 
-            #[inline]
-            fn visit_seq<V>(self, mut visitor: V) -> Result<Item, V::Error>
-            where
-                V: SeqAccess<'de>,
-            {
-                let mut vec = Vec::new();
-                while let Some(elem) = visitor.next_element()? {
-                    vec.push(elem);
-                }
-                Ok(Item::List(vec))
-            }
+    #[inline]
+    fn visit_i16<E>(self, value: i16) -> Result<Item, E> {
+        Ok(Item::Int16(value))
+    }
 
-            #[inline]
-            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(Item::Blob(v.to_owned()))
-            }
+    #[inline]
+    fn visit_i8<E>(self, value: i8) -> Result<Item, E> {
+        Ok(Item::Int16(value.into()))
+    }
 
-            #[inline]
-            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(Item::Blob(v))
-            }
+    #[inline]
+    fn visit_u32<E>(self, value: u32) -> Result<Item, E> {
+        Ok(Item::Int64(value.into()))
+    }
 
-            #[inline]
-            fn visit_map<V>(self, mut visitor: V) -> Result<Item, V::Error>
-            where
-                V: de::MapAccess<'de>,
-            {
-                let mut map = Map::new();
+    #[inline]
+    fn visit_u16<E>(self, value: u16) -> Result<Item, E> {
+        Ok(Item::Int64(value.into()))
+    }
 
-                while let Some(key) = visitor.next_key()? {
-                    let val = visitor.next_value()?;
-                    map.insert(key, val);
-                }
+    #[inline]
+    fn visit_u8<E>(self, value: u8) -> Result<Item, E> {
+        if let Some(count) = self.u8_count {
+            count.set(count.get() + 1);
+        }
+        Ok(Item::Int16(value.into()))
+    }
 
-                Ok(Item::Map(map))
-            }
+    #[inline]
+    fn visit_f32<E>(self, value: f32) -> Result<Item, E> {
+        Ok(Item::F32(value))
+    }
+
+    #[inline]
+    fn visit_f64<E>(self, value: f64) -> Result<Item, E> {
+        Ok(Item::F64(value))
+    }
+
+    #[inline]
+    fn visit_string<E>(self, value: String) -> Result<Item, E> {
+        Ok(Item::String(value))
+    }
+
+    #[inline]
+    fn visit_str<E>(self, value: &str) -> Result<Item, E>
+    where
+        E: de::Error,
+    {
+        self.visit_string(String::from(value))
+    }
+
+    #[inline]
+    fn visit_seq<V>(self, mut visitor: V) -> Result<Item, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        let u8_count = Cell::new(0);
+        let seed = || Element {
+            u8_seq_as_blob: self.u8_seq_as_blob,
+            u8_count: &u8_count,
+        };
+
+        let mut vec = Vec::new();
+        while let Some(elem) = visitor.next_element_seed(seed())? {
+            vec.push(elem);
         }
 
-        de.deserialize_any(ItemVisitor)
+        if self.u8_seq_as_blob && !vec.is_empty() && u8_count.get() == vec.len() {
+            let data = vec
+                .iter()
+                .filter_map(|item| item.as_int16().and_then(|n| u8::try_from(*n).ok()))
+                .collect();
+            return Ok(Item::Blob(data));
+        }
+        Ok(Item::List(vec))
+    }
+
+    #[inline]
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Item::Blob(v.to_owned()))
+    }
+
+    #[inline]
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Item::Blob(v))
+    }
+
+    #[inline]
+    fn visit_map<V>(self, mut visitor: V) -> Result<Item, V::Error>
+    where
+        V: de::MapAccess<'de>,
+    {
+        let mut map = Map::new();
+
+        while let Some(key) = visitor.next_key()? {
+            let val =
+                visitor.next_value_seed(ItemSeed::new().u8_seq_as_blob(self.u8_seq_as_blob))?;
+            map.insert(key, val);
+        }
+
+        Ok(Item::Map(map))
     }
 }
 
@@ -168,6 +249,7 @@ impl Serialize for Item {
             Item::F64(v) => s.serialize_f64(v),
             Item::String(ref v) => s.serialize_str(v),
             Item::Blob(ref v) => Bytes::new(&v[..]).serialize(s),
+            Item::CompressedBlob(ref blob) => Bytes::new(&blob.data[..]).serialize(s),
             Item::List(ref array) => {
                 let mut state = s.serialize_seq(Some(array.len()))?;
                 for item in array {
@@ -260,3 +342,43 @@ fn serde_de_test() {
         item
     );
 }
+
+#[test]
+fn serde_de_u8_seq_test() {
+    use serde_value::Value;
+    use std::collections::BTreeMap;
+
+    let buf = serde_bytes::ByteBuf::from(vec![1, 2, 3]);
+    let item = Item::deserialize(serde_value::to_value(buf).unwrap()).unwrap();
+    assert_eq!(item, Item::Blob(vec![1, 2, 3]));
+
+    let bytes = || Value::Seq(vec![Value::U8(1), Value::U8(255)]);
+    let blob_seed = ItemSeed::new().u8_seq_as_blob(true);
+    let item = Item::deserialize(serde_value::ValueDeserializer::<de::value::Error>::new(
+        bytes(),
+    ));
+    assert_eq!(item, Ok(Item::List(vec![Item::Int16(1), Item::Int16(255)])));
+    let item = blob_seed.deserialize(serde_value::ValueDeserializer::<de::value::Error>::new(
+        bytes(),
+    ));
+    assert_eq!(item, Ok(Item::Blob(vec![1, 255])));
+
+    // nested sequences too, but not ones with other integer types
+    let value = Value::Map(BTreeMap::from_iter([(
+        Value::String(String::from("data")),
+        Value::Seq(vec![bytes(), Value::Seq(vec![Value::U8(1), Value::I16(2)])]),
+    )]));
+    let item = blob_seed.deserialize(serde_value::ValueDeserializer::<de::value::Error>::new(
+        value,
+    ));
+    assert_eq!(
+        item,
+        Ok(Item::map([(
+            String::from("data"),
+            Item::list([
+                Item::Blob(vec![1, 255]),
+                Item::list([Item::Int16(1), Item::Int16(2)])
+            ])
+        )]))
+    );
+}
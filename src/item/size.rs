@@ -0,0 +1,57 @@
+// the number of bytes the `Encoder` writes for an item with its default settings, computed without
+// writing anything. blobs depend on where they start because of the alignment
+
+use crate::consts;
+use crate::{Compression, Item};
+
+/// the bytes of `item` when it starts at `position` in the stream
+pub(crate) fn item_size(item: &Item, position: usize) -> usize {
+    match item {
+        Item::Void | Item::Bool(_) => 1,
+        Item::Int16(_) => 3,
+        Item::Int64(_) | Item::F64(_) => 9,
+        Item::F32(_) => 5,
+        Item::String(text) => 1 + string_size(text),
+        Item::List(list) => {
+            let mut end = position + 1 + size_size(list.len());
+            for item in list {
+                end += item_size(item, end);
+            }
+            end - position
+        }
+        Item::Map(map) => {
+            let extension = item.extension_name().map_or(0, string_size);
+            let mut end = position + 1 + extension + size_size(map.len());
+            for (key, item) in map {
+                end += string_size(key);
+                end += item_size(item, end);
+            }
+            end - position
+        }
+        Item::Blob(data) => blob_size(data.len(), 0, true, position),
+        Item::CompressedBlob(blob) => {
+            let aligned = blob.compression == Compression::None;
+            blob_size(blob.data.len(), blob.spare, aligned, position)
+        }
+    }
+}
+
+/// the three sizes, the compression byte and the checksum byte come before the alignment byte.
+/// compressed data is not aligned, its size is only known after compressing so it counts as the
+/// uncompressed size. the `spare` allocated bytes follow the data
+fn blob_size(len: usize, spare: usize, aligned: bool, position: usize) -> usize {
+    let header = 1 + size_size(len + spare) + 2 * size_size(len) + 2;
+    let padding = match aligned {
+        true => (8 - (position + header + 1) % 8) % 8,
+        false => 0,
+    };
+    header + 1 + padding + len + spare
+}
+
+fn string_size(text: &str) -> usize {
+    size_size(text.len()) + text.len()
+}
+
+fn size_size(size: usize) -> usize {
+    consts::encode_size(size, &mut [0; 9]).len()
+}
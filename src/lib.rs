@@ -1,13 +1,35 @@
 // https://bsdf.readthedocs.io/spec.html#minimal-implementation
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+#[cfg(test)]
+use alloc::vec;
+use alloc::vec::Vec;
+
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum InvalidExtension {
     #[error("zlib is not included")]
     ZlibNotCompiled,
     #[error("bz2 is not included")]
     Bz2NotCompiled,
+    #[error("md5 is not included")]
+    Md5NotCompiled,
+    #[error("lz4 is not included")]
+    Lz4NotCompiled,
+    #[error("crc32 is not included")]
+    Crc32NotCompiled,
+    #[error("half is not included")]
+    HalfNotCompiled,
     #[error("invalid compression setting")]
     InvalidCompressionSetting(u8),
+    #[error("invalid checksum setting")]
+    InvalidChecksumSetting(u8),
+    /// a value encoded by the extension of this name, there is no support for decoding extensions
+    #[error("extension {0:?} is not supported")]
+    Unsupported(alloc::string::String),
 }
 
 impl From<InvalidExtension> for Error {
@@ -22,18 +44,100 @@ pub enum Error {
     MissingData,
     #[error("invalid header")]
     InvalidHeader,
+    #[error("unsupported version {major}.{minor}")]
+    UnsupportedVersion { major: u8, minor: u8 },
     #[error("sudden missing data")]
     Eof,
     #[error("invalid size byte")]
     InvalidSize,
+    #[error("invalid type byte {0:#04x}")]
+    InvalidType(u8),
+    /// holds the bytes of the string
     #[error("String is not utf8")]
-    InvalidUtf8,
+    InvalidUtf8(Vec<u8>),
     #[error("invalid blob hash")]
     InvalidBlobHash,
+    #[error("blob used size exceeds its allocated size")]
+    InvalidBlob,
+    #[error("declared size exceeds the parser limits")]
+    LimitExceeded,
+    #[error("lists and maps are nested deeper than the parser limits")]
+    DepthExceeded,
+    #[error("key {0:?} appears more than once in a map")]
+    DuplicateKey(alloc::string::String),
+    /// from `Decoder`, an open stream only ends with the data so it can't be handed out
+    #[error("open streams can't be decoded incrementally")]
+    OpenStream,
+    /// the number of bytes after the document, from `Parser::parse_complete`
+    #[error("{0} bytes after the end of the document")]
+    TrailingData(usize),
     #[error("invalid extension")]
     InvalidExtension(InvalidExtension),
+    #[error("expected {expected}, found {found}")]
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error("integer {0} is out of range")]
+    IntegerOutOfRange(i64),
+    #[error("list declared {declared} items but {written} were written")]
+    LengthMismatch { declared: usize, written: usize },
+    #[cfg(feature = "std")]
     #[error("reading data from reader went wrong")]
-    Reader(#[from] std::io::Error),
+    Reader(#[source] std::io::Error),
+    #[cfg(feature = "std")]
+    #[error("writing data to writer went wrong")]
+    Writer(std::io::Error),
+    #[cfg(feature = "with-serde")]
+    #[error("{0}")]
+    Serde(alloc::string::String),
+    /// a json value that has no BSDF counterpart
+    #[cfg(feature = "json")]
+    #[error("{0}")]
+    Json(alloc::string::String),
+    /// the text passed to `from_base64` is not base64, the BSDF data was not looked at yet
+    #[cfg(feature = "base64")]
+    #[error("invalid base64: {0}")]
+    Base64(base64::DecodeError),
+    #[error("{source} at byte {offset}")]
+    At { offset: usize, source: Box<Error> },
+}
+
+impl Error {
+    /// the byte offset in the stream where the parser failed
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::At { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// whether the data ended in the middle of a document, so more bytes might still make it
+    /// parse. corrupt data is never incomplete
+    pub fn is_incomplete(&self) -> bool {
+        match self {
+            Error::Eof | Error::MissingData => true,
+            #[cfg(feature = "std")]
+            Error::Reader(e) => e.kind() == std::io::ErrorKind::UnexpectedEof,
+            Error::At { source, .. } => source.is_incomplete(),
+            _ => false,
+        }
+    }
+
+    /// the error without the position it happened at
+    pub fn into_inner(self) -> Error {
+        match self {
+            Error::At { source, .. } => *source,
+            other => other,
+        }
+    }
+
+    pub(crate) fn type_mismatch(expected: &'static str, found: &Item) -> Error {
+        Error::TypeMismatch {
+            expected,
+            found: found.type_name(),
+        }
+    }
 }
 
 impl PartialEq for Error {
@@ -42,23 +146,235 @@ impl PartialEq for Error {
         match (self, other) {
             (MissingData, MissingData) => true,
             (InvalidHeader, InvalidHeader) => true,
+            (
+                UnsupportedVersion { major: a, minor: b },
+                UnsupportedVersion { major: c, minor: d },
+            ) => a == c && b == d,
             (Eof, Eof) => true,
             (InvalidSize, InvalidSize) => true,
-            (InvalidUtf8, InvalidUtf8) => true,
+            (InvalidType(a), InvalidType(b)) => a == b,
+            (InvalidUtf8(a), InvalidUtf8(b)) => a == b,
             (InvalidBlobHash, InvalidBlobHash) => true,
+            (InvalidBlob, InvalidBlob) => true,
+            (LimitExceeded, LimitExceeded) => true,
+            (DepthExceeded, DepthExceeded) => true,
+            (DuplicateKey(a), DuplicateKey(b)) => a == b,
+            (OpenStream, OpenStream) => true,
+            (TrailingData(a), TrailingData(b)) => a == b,
             (InvalidExtension(e), InvalidExtension(f)) if e == f => true,
+            (
+                TypeMismatch {
+                    expected: a,
+                    found: b,
+                },
+                TypeMismatch {
+                    expected: c,
+                    found: d,
+                },
+            ) => a == c && b == d,
+            (IntegerOutOfRange(a), IntegerOutOfRange(b)) => a == b,
+            (
+                LengthMismatch {
+                    declared: a,
+                    written: b,
+                },
+                LengthMismatch {
+                    declared: c,
+                    written: d,
+                },
+            ) => a == c && b == d,
+            #[cfg(feature = "std")]
             (Reader(e), Reader(f)) if e.kind() == f.kind() => true,
+            #[cfg(feature = "std")]
+            (Writer(e), Writer(f)) if e.kind() == f.kind() => true,
+            #[cfg(feature = "with-serde")]
+            (Serde(a), Serde(b)) => a == b,
+            #[cfg(feature = "json")]
+            (Json(a), Json(b)) => a == b,
+            #[cfg(feature = "base64")]
+            (Base64(a), Base64(b)) => a == b,
+            (
+                At {
+                    offset: a,
+                    source: b,
+                },
+                At {
+                    offset: c,
+                    source: d,
+                },
+            ) => a == c && b == d,
             _ => false,
         }
     }
 }
 
+/// a reader that runs out of data in the middle of a value gives `Error::Eof`, like the end of the
+/// data does. every other io error is kept as `Error::Reader`
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Error {
+        match err.kind() {
+            std::io::ErrorKind::UnexpectedEof => Error::Eof,
+            _ => Error::Reader(err),
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        match err {}
+    }
+}
+
+pub mod blob;
+pub mod compression;
 pub mod consts;
+pub mod decoder;
+#[cfg(feature = "std")]
+pub mod encoder;
+pub mod io;
 pub mod parser;
 pub mod item;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+mod reader;
+pub mod visitor;
+
+pub use blob::{Blob, BlobMeta};
+pub use compression::Compression;
+pub use decoder::Decoder;
+#[cfg(feature = "std")]
+pub use encoder::{Encoder, FloatEncoding, IntEncoding, ListWriter};
+#[cfg(feature = "async")]
+pub use parser::AsyncParser;
+pub use parser::{Endian, Limits, ListIter, MapIter, Parser, ParserBuilder};
+#[cfg(feature = "ndarray")]
+pub use item::Dtype;
+#[cfg(feature = "with-serde")]
+pub use item::ItemSeed;
+pub use item::{BorrowedItem, BorrowedMap, FromItem, HashableItem, Item, Map, MapLike};
+#[cfg(feature = "mmap")]
+pub use mmap::{from_mmap, MappedDocument};
+pub use visitor::Visitor;
+
+use io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+/// an indented dump of `item` for debugging, one line per value with its type. unlike
+/// `Display` this shows the structure, long strings are cut off and blobs only show their size
+pub fn debug_tree(item: &Item) -> alloc::string::String {
+    let mut out = alloc::string::String::new();
+    item::display::write_tree(&mut out, item, 0).expect("writing to a string can't fail");
+    out
+}
+
+/// the differences between `a` and `b`, one line per differing value with its path like
+/// `nested.list[1]: Bool(false) != Bool(true)`. empty when the items are the same, meant for
+/// readable test failures
+pub fn diff(a: &Item, b: &Item) -> Vec<alloc::string::String> {
+    let mut differences = Vec::new();
+    item::diff::diff(a, b, &mut alloc::string::String::new(), &mut differences);
+    differences
+}
+
+/// the number of bytes `to_vec` writes for `item`, without encoding it. exact as long as the
+/// blobs are not compressed, a compressed blob counts with its uncompressed size
+pub fn encoded_size(item: &Item) -> usize {
+    let header = consts::PREFIX.len() + 2;
+    header + item::size::item_size(item, header)
+}
+
+/// whether zlib compressed blobs can be read and written, the `zlib` feature
+pub fn supports_zlib() -> bool {
+    cfg!(feature = "zlib")
+}
+
+/// whether bz2 compressed blobs can be read and written, the `bz2` feature
+pub fn supports_bz2() -> bool {
+    cfg!(feature = "bz2")
+}
+
+/// whether lz4 compressed blobs can be read and written, the `lz4` feature
+pub fn supports_lz4() -> bool {
+    cfg!(feature = "lz4")
+}
+
+/// whether md5 blob checksums can be verified and written, the `md5` feature
+pub fn supports_md5() -> bool {
+    cfg!(feature = "md5")
+}
+
+/// whether crc32 blob checksums can be verified and written, the `crc32` feature
+pub fn supports_crc32() -> bool {
+    cfg!(feature = "crc32")
+}
+
+pub fn from_slice(data: &[u8]) -> Result<Option<Item>, Error> {
+    from_reader(data)
+}
+
+pub fn from_reader<R: Read>(reader: R) -> Result<Option<Item>, Error> {
+    Parser::new(reader).parse()
+}
+
+#[cfg(feature = "std")]
+pub fn to_vec(item: &Item) -> Result<Vec<u8>, Error> {
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, item)?;
+    Ok(buffer)
+}
+
+#[cfg(feature = "std")]
+pub fn to_writer<W: Write>(writer: W, item: &Item) -> Result<(), Error> {
+    Encoder::new(writer).write(item)
+}
+
+/// the document of `item` as standard base64 with padding, for embedding it in text
+#[cfg(feature = "base64")]
+pub fn to_base64(item: &Item) -> Result<alloc::string::String, Error> {
+    use base64::Engine;
+
+    Ok(base64::engine::general_purpose::STANDARD.encode(to_vec(item)?))
+}
+
+/// the counterpart of `to_base64`, text that is not base64 fails with `Error::Base64`
+#[cfg(feature = "base64")]
+pub fn from_base64(text: &str) -> Result<Option<Item>, Error> {
+    use base64::Engine;
+
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(text)
+        .map_err(Error::Base64)?;
+    from_slice(&data)
+}
+
+/// converts any `Serialize` type into an `Item`, like `serde_json::to_value`
+#[cfg(feature = "with-serde")]
+pub fn to_item<T: serde::Serialize + ?Sized>(value: &T) -> Result<Item, Error> {
+    value.serialize(item::ser::Serializer)
+}
+
+/// converts an `Item` into any `Deserialize` type, like `serde_json::from_value`
+#[cfg(feature = "with-serde")]
+pub fn from_item<T: serde::de::DeserializeOwned>(item: Item) -> Result<T, Error> {
+    T::deserialize(item)
+}
+
+/// deserializes a `T` straight from BSDF bytes, without building an `Item` first
+#[cfg(feature = "with-serde")]
+pub fn from_bsdf_slice<T: serde::de::DeserializeOwned>(data: &[u8]) -> Result<T, Error> {
+    Parser::new(data).deserialize()
+}
 
-pub use parser::Parser;
-pub use item::{Item, Map};
+/// serializes `value` straight to BSDF bytes, without building an `Item` first
+#[cfg(feature = "with-serde")]
+pub fn to_bsdf_vec<T: serde::Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut buffer = Vec::new();
+    Encoder::new(&mut buffer).serialize(value)?;
+    Ok(buffer)
+}
 
 #[test]
 fn item_as_test() {
@@ -73,3 +389,268 @@ fn item_as_test() {
 
     assert_eq!(item, expected);
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn encoded_size_test() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    let item = from_slice(data).unwrap().unwrap();
+
+    assert_eq!(encoded_size(&item), data.len());
+    assert_eq!(encoded_size(&item), to_vec(&item).unwrap().len());
+
+    let items = [
+        Item::String("x".repeat(300)),
+        Item::list((0..10).map(|n| Item::Blob(vec![0; n]))),
+        Item::map([
+            (String::from("blob"), Item::Blob(vec![1; 260])),
+            ("k".repeat(251), Item::F32(1.0)),
+        ]),
+        Item::Int64(1),
+        Item::F64(1.0),
+        Item::Void,
+    ];
+    for item in items {
+        assert_eq!(encoded_size(&item), to_vec(&item).unwrap().len());
+    }
+}
+
+#[test]
+fn diff_test() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    let a = from_slice(data).unwrap().unwrap();
+    let mut b = a.clone();
+    assert!(diff(&a, &b).is_empty());
+
+    *b.pointer_mut("/nested/list/1").unwrap() = Item::Bool(true);
+    assert_eq!(
+        diff(&a, &b),
+        vec!["nested.list[1]: Bool(false) != Bool(true)"]
+    );
+
+    let c = Item::list([Item::Int16(1)]);
+    let d = Item::list([Item::Int64(1), Item::Void]);
+    assert_eq!(
+        diff(&c, &d),
+        vec!["[0]: Int16(1) != Int64(1)", "[1]: <missing> != Void"]
+    );
+    assert_eq!(
+        diff(&Item::F64(f64::NAN), &Item::F64(f64::NAN)),
+        Vec::<alloc::string::String>::new()
+    );
+}
+
+#[test]
+fn supports_test() {
+    assert_eq!(supports_zlib(), cfg!(feature = "zlib"));
+    assert_eq!(supports_bz2(), cfg!(feature = "bz2"));
+    assert_eq!(supports_lz4(), cfg!(feature = "lz4"));
+    assert_eq!(supports_md5(), cfg!(feature = "md5"));
+    assert_eq!(supports_crc32(), cfg!(feature = "crc32"));
+}
+
+#[test]
+fn is_incomplete_test() {
+    let error = from_slice(b"BSDF\x02\x02h\x01").unwrap_err();
+    assert!(error.is_incomplete());
+    assert!(from_slice(b"BSD").unwrap_err().is_incomplete());
+    #[cfg(feature = "std")]
+    assert!(Error::Reader(std::io::ErrorKind::UnexpectedEof.into()).is_incomplete());
+
+    let error = from_slice(b"BSDF\x02\x02s\x01\xff").unwrap_err();
+    assert!(!error.is_incomplete());
+    assert_eq!(error.into_inner(), Error::InvalidUtf8(vec![0xff]));
+    assert!(!Error::InvalidHeader.is_incomplete());
+    assert!(!Error::InvalidSize.is_incomplete());
+    assert!(!Error::InvalidBlobHash.is_incomplete());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn to_vec_from_slice_test() {
+    let item = Item::Map(Map::from_iter([
+        (String::from("test"), Item::Int16(1)),
+        (
+            String::from("nested"),
+            Item::Map(Map::from_iter([
+                (String::from("nested"), Item::Bool(true)),
+                (
+                    String::from("list"),
+                    Item::List(vec![
+                        Item::Int16(-1),
+                        Item::Bool(false),
+                        Item::Int64(123456789),
+                    ]),
+                ),
+                (
+                    String::from("data"),
+                    Item::String(String::from("some text")),
+                ),
+            ])),
+        ),
+    ]));
+
+    let data = to_vec(&item).unwrap();
+
+    assert_eq!(from_slice(&data).unwrap(), Some(item));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn to_writer_from_reader_test() {
+    let item = Item::List(vec![Item::F64(1.5), Item::Blob(vec![1, 2, 3])]);
+
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, &item).unwrap();
+
+    assert_eq!(from_reader(buffer.as_slice()).unwrap(), Some(item));
+}
+
+#[cfg(all(test, feature = "with-serde"))]
+mod serde_test {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Kind {
+        Plain,
+        Sized(u32),
+        Point { x: f32, y: f32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        name: String,
+        count: i16,
+        id: u64,
+        tags: Vec<String>,
+        parent: Option<String>,
+        #[serde(with = "serde_bytes")]
+        data: Vec<u8>,
+        kinds: Vec<Kind>,
+    }
+
+    #[test]
+    fn to_item_from_item_test() {
+        let record = Record {
+            name: String::from("first"),
+            count: -3,
+            id: 1234567,
+            tags: vec![String::from("a"), String::from("b")],
+            parent: None,
+            data: vec![1, 2, 3],
+            kinds: vec![Kind::Plain, Kind::Sized(5), Kind::Point { x: 1.0, y: 2.5 }],
+        };
+
+        let item = to_item(&record).unwrap();
+        assert_eq!(item["name"], Item::from("first"));
+        assert_eq!(item["count"], Item::Int16(-3));
+        assert_eq!(item["id"], Item::Int64(1234567));
+        assert_eq!(item["parent"], Item::Void);
+        assert_eq!(item["data"], Item::Blob(vec![1, 2, 3]));
+        assert_eq!(item["kinds"][0], Item::from("Plain"));
+        assert_eq!(
+            item["kinds"][1],
+            Item::map([(String::from("Sized"), Item::Int64(5))])
+        );
+
+        assert_eq!(from_item::<Record>(item), Ok(record));
+    }
+
+    #[test]
+    fn from_bsdf_slice_test() {
+        use std::collections::HashMap;
+
+        // copied from python
+        let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+
+        let map: HashMap<String, i16> = from_bsdf_slice(data).unwrap();
+        assert_eq!(
+            map,
+            HashMap::from([
+                (String::from("test"), 1),
+                (String::from("test1"), 2),
+                (String::from("test3"), 4)
+            ])
+        );
+
+        assert_eq!(
+            from_bsdf_slice::<HashMap<String, bool>>(data).map_err(Error::into_inner),
+            Err(Error::Serde(String::from(
+                "invalid type: integer `1`, expected a boolean"
+            )))
+        );
+    }
+
+    #[test]
+    fn to_bsdf_vec_test() {
+        let record = Record {
+            name: String::from("first"),
+            count: -3,
+            id: 1234567,
+            tags: vec![String::from("a"), String::from("b")],
+            parent: Some(String::from("root")),
+            data: vec![1, 2, 3],
+            kinds: vec![Kind::Plain, Kind::Sized(5), Kind::Point { x: 1.0, y: 2.5 }],
+        };
+
+        let data = to_bsdf_vec(&record).unwrap();
+
+        // same document as going through an item
+        assert_eq!(from_slice(&data), Ok(Some(to_item(&record).unwrap())));
+        assert_eq!(from_bsdf_slice::<Record>(&data), Ok(record));
+    }
+
+    #[test]
+    fn to_bsdf_vec_unknown_length_test() {
+        struct Numbers;
+
+        impl Serialize for Numbers {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                s.collect_seq((0..3).filter(|n| n % 2 == 0))
+            }
+        }
+
+        let data = to_bsdf_vec(&Numbers).unwrap();
+        assert_eq!(
+            data,
+            b"BSDF\x02\x02l\x02i\x00\x00\x00\x00\x00\x00\x00\x00i\x02\x00\x00\x00\x00\x00\x00\x00"
+        );
+        assert_eq!(from_bsdf_slice::<Vec<i64>>(&data), Ok(vec![0, 2]));
+    }
+
+    #[test]
+    fn from_item_errors_test() {
+        assert!(from_item::<i16>(Item::from("text")).is_err());
+        assert!(from_item::<u8>(Item::Int64(300)).is_err());
+        assert!(to_item(&u64::MAX).is_err());
+        assert!(to_item(&std::collections::HashMap::from([(1, 2)])).is_err());
+    }
+}
+
+#[cfg(all(test, feature = "base64"))]
+mod base64_test {
+    use super::*;
+
+    #[test]
+    fn base64_round_trip() {
+        // copied from python
+        let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+        let item = from_slice(data).unwrap().unwrap();
+
+        let text = to_base64(&item).unwrap();
+        assert_eq!(from_base64(&text), Ok(Some(item)));
+    }
+
+    #[test]
+    fn base64_invalid() {
+        assert!(matches!(from_base64("not base64!"), Err(Error::Base64(_))));
+        // valid base64, but not a document
+        assert_eq!(
+            from_base64("AAAAAAAA").map_err(Error::into_inner),
+            Err(Error::InvalidHeader)
+        );
+    }
+}
@@ -0,0 +1,78 @@
+// parsing straight over a memory mapped file, the slice parser borrows strings and blobs from the
+// map instead of copying them
+
+use crate::{BorrowedItem, Error, Item, Parser};
+use memmap2::Mmap;
+use std::fs::File;
+use std::path::Path;
+
+/// a file mapped into memory, the items borrowed from it can't outlive it
+#[derive(Debug)]
+pub struct MappedDocument {
+    map: Mmap,
+}
+
+impl MappedDocument {
+    /// the mapped bytes of the file
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// parses the document into an item that owns its data
+    pub fn parse(&self) -> Result<Option<Item>, Error> {
+        Parser::from_bytes(self.as_bytes()).parse()
+    }
+
+    /// parses the document with `Parser::parse_borrowed`, strings and uncompressed blobs point
+    /// into the map
+    pub fn parse_borrowed(&self) -> Result<Option<BorrowedItem<'_>>, Error> {
+        Parser::from_bytes(self.as_bytes()).parse_borrowed()
+    }
+}
+
+/// maps the file at `path` into memory for parsing it without reading it first
+///
+/// # Safety
+///
+/// the file must not be changed or truncated while the `MappedDocument` or anything borrowed from
+/// it is alive, by this process or another one. the borrows are only checked against the map,
+/// the operating system doesn't stop others from writing to the file
+pub unsafe fn from_mmap<P: AsRef<Path>>(path: P) -> Result<MappedDocument, Error> {
+    let file = File::open(path)?;
+    let map = Mmap::map(&file)?;
+    Ok(MappedDocument { map })
+}
+
+#[test]
+fn mmap_test() {
+    use alloc::borrow::Cow;
+
+    // copied from python
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    let path = std::env::temp_dir().join(alloc::format!("bsdf-mmap-{}.bsdf", std::process::id()));
+    std::fs::write(&path, data).unwrap();
+
+    // nothing else knows the file
+    let document = unsafe { from_mmap(&path) }.unwrap();
+    let item = document.parse().unwrap().unwrap();
+    assert_eq!(item["nested"]["list"][2], Item::Int64(123456789));
+
+    let borrowed = document.parse_borrowed().unwrap().unwrap();
+    let text = match &borrowed {
+        BorrowedItem::Map(map) => match &map["nested"] {
+            BorrowedItem::Map(nested) => nested["data"].clone(),
+            _ => panic!("nested is a map"),
+        },
+        _ => panic!("fixture is a map"),
+    };
+    assert!(matches!(
+        text,
+        BorrowedItem::String(Cow::Borrowed("some text"))
+    ));
+    assert_eq!(document.as_bytes(), data);
+
+    drop(document);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(unsafe { from_mmap(&path) }, Err(Error::Reader(_))));
+}
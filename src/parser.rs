@@ -1,329 +1,2171 @@
-
 // everything is little endian
 
+use crate::blob::{self, Checksum};
 use crate::consts;
-use crate::{Error, InvalidExtension, Item, Map};
-use std::io::Bytes;
-use std::io::Read;
-use std::iter::Peekable;
+use crate::io::Read;
+use crate::item;
+use crate::reader::ByteReader;
+use crate::visitor::{TreeBuilder, Visitor};
+#[cfg(test)]
+use crate::Map;
+use crate::{
+    Blob, BlobMeta, BorrowedItem, BorrowedMap, Compression, Error, FromItem, Item, MapLike,
+};
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(test)]
+use alloc::{string::ToString, vec};
+
+#[cfg(feature = "async")]
+mod async_impl;
+mod builder;
+#[cfg(feature = "with-serde")]
+mod serde_impl;
+
+#[cfg(feature = "async")]
+pub use async_impl::AsyncParser;
+pub use builder::ParserBuilder;
+
+// declared lengths are only trusted up to this many elements when reserving memory up front,
+// larger collections grow while they are read
+pub(crate) const MAX_PREALLOCATE: usize = 4096;
+
+/// caps on the sizes declared in the stream, so untrusted input can't make the parser allocate
+/// arbitrary amounts of memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// maximum number of elements in a single list or map
+    pub max_length: usize,
+    /// maximum number of bytes allocated for a whole document
+    pub max_bytes: usize,
+    /// maximum nesting of lists and maps, parsing recurses so this bounds the stack usage
+    pub max_depth: usize,
+}
+
+/// the byte order numbers and sizes are read in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endian {
+    /// what the format specifies
+    #[default]
+    Little,
+    /// for debugging data from a producer that wrongly wrote its native big endian order
+    Big,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_length: 1 << 24,
+            max_bytes: 1 << 30,
+            max_depth: 256,
+        }
+    }
+}
 
-pub struct Parser<'a> {
+pub struct Parser<R: Read> {
     version: Option<u16>,
-    reader: Peekable<Bytes<Box<dyn Read + 'a>>>,
+    reader: ByteReader<R>,
+    limits: Limits,
+    allocated: usize,
+    invalid_utf8_as_blob: bool,
+    utf8_lossy: bool,
+    reject_duplicate_keys: bool,
+    // lists and maps the parser is currently inside of
+    depth: usize,
+    verify_checksums: bool,
+    keep_blob_compression: bool,
+    widen_integers: bool,
+    endian: Endian,
+    // set by `peek_type`, the next parse skips the header
+    header_read: bool,
+    // the caller's buffer during `parse_with_scratch`
+    scratch: Option<Vec<u8>>,
 }
 
-impl<'a> Parser<'a> {
-    pub fn new(reader: Box<dyn Read + 'a>) -> Parser<'a> {
+impl<R: Read> Parser<R> {
+    pub fn new(reader: R) -> Parser<R> {
+        Self::with_limits(reader, Limits::default())
+    }
+
+    pub fn with_limits(reader: R, limits: Limits) -> Parser<R> {
         Parser {
-            reader: reader.bytes().peekable(),
+            // the parser consumes exactly the bytes of the document, buffering would read past it
+            reader: ByteReader::new(reader),
             version: None,
+            limits,
+            allocated: 0,
+            invalid_utf8_as_blob: false,
+            utf8_lossy: false,
+            reject_duplicate_keys: false,
+            depth: 0,
+            verify_checksums: cfg!(feature = "md5"),
+            keep_blob_compression: false,
+            widen_integers: false,
+            endian: Endian::Little,
+            header_read: false,
+            scratch: None,
         }
     }
 
+    /// whether blob checksums are checked, defaults to whether the `md5` feature is enabled.
+    /// verifying without the feature of the checksum fails on the first blob that has one
+    pub fn verify_checksums(mut self, verify: bool) -> Self {
+        self.verify_checksums = verify;
+        self
+    }
+
+    /// return blobs as `Item::CompressedBlob` with the compression and spare allocated bytes they
+    /// were stored with, so writing the item again keeps every blob laid out the way it was. the
+    /// data is still decompressed. this holds for uncompressed blobs too, so `Item::as_blob` is
+    /// `None` for every blob, use `Item::as_compressed_blob` instead
+    pub fn keep_blob_compression(mut self, keep: bool) -> Self {
+        self.keep_blob_compression = keep;
+        self
+    }
+
+    /// return every `Int16` as `Int64`, so the tree only has one integer variant. writing it again
+    /// no longer gives the same bytes
+    pub fn widen_integers(mut self, widen: bool) -> Self {
+        self.widen_integers = widen;
+        self
+    }
+
+    /// the byte order of integers, floats and sizes, the header version is not affected. anything
+    /// but the default `Endian::Little` goes against the spec and is only meant for diagnosing
+    /// data from a broken producer
+    pub fn endianness(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    /// return string values that are not valid utf8 as `Item::Blob` instead of failing with
+    /// `Error::InvalidUtf8`. map keys have to be strings, so those still fail
+    pub fn invalid_utf8_as_blob(mut self, enabled: bool) -> Self {
+        self.invalid_utf8_as_blob = enabled;
+        self
+    }
+
+    /// decode strings and map keys that are not valid utf8 with replacement characters instead of
+    /// failing with `Error::InvalidUtf8`, this goes before `invalid_utf8_as_blob`
+    pub fn utf8_lossy(mut self, enabled: bool) -> Self {
+        self.utf8_lossy = enabled;
+        self
+    }
+
+    /// steps over the root value of the document without building it, the header is parsed first
+    /// unless `peek_type` already did. strings and blobs are skipped without reading them into
+    /// memory and blobs are not decompressed. the next `parse` starts at the following document.
+    /// to skip values inside a document use `MapIter::skip_value`
+    pub fn skip_item(&mut self) -> Result<(), Error> {
+        self.at_offset(|parser| {
+            parser.parse_header()?;
+            if !parser.skip_value()? {
+                return Err(Error::MissingData);
+            }
+            Ok(())
+        })
+    }
+
+    /// fail with `Error::DuplicateKey` when a key appears twice in one map, by default the last
+    /// value wins
+    pub fn reject_duplicate_keys(mut self, reject: bool) -> Self {
+        self.reject_duplicate_keys = reject;
+        self
+    }
+
+    /// swaps in the next reader so one parser can be used for many documents. the settings and
+    /// limits are kept, everything read from the previous reader is forgotten
+    pub fn reset(&mut self, reader: R) {
+        self.reader = ByteReader::new(reader);
+        self.version = None;
+        self.allocated = 0;
+        self.depth = 0;
+        self.header_read = false;
+    }
+
+    /// the (major, minor) version from the header, available once the header is parsed
+    pub fn version(&self) -> Option<(u8, u8)> {
+        self.version.map(|version| {
+            let [major, minor] = version.to_le_bytes();
+            (major, minor)
+        })
+    }
+
+    /// the number of bytes of the reader the parser used so far. after a `parse` this is where the
+    /// document ends, the bytes after it are not touched
+    pub fn bytes_consumed(&self) -> usize {
+        self.reader.position()
+    }
+
+    /// parses the header and the root value. a header with nothing after it is an empty document
+    /// and gives `Ok(None)`, a byte after the header that is not a type is `Error::InvalidType`
     pub fn parse(&mut self) -> Result<Option<Item>, Error> {
-        self.parse_header()?;
-        Ok(self.parse_item()?)
+        self.at_offset(|parser| {
+            parser.parse_header()?;
+            parser.parse_item()
+        })
     }
 
-    fn parse_header(&mut self) -> Result<(), Error> {
-        let first_bytes: Result<Vec<u8>, _> = self.reader.by_ref().take(6).collect();
-        let buffer = first_bytes?;
+    /// like `parse`, but string and blob payloads are read into `scratch` instead of a fresh buffer
+    /// each. the strings and blobs of the item are still owned, reusing the same scratch buffer
+    /// across parses saves growing the intermediate buffer every time
+    pub fn parse_with_scratch(&mut self, scratch: &mut Vec<u8>) -> Result<Option<Item>, Error> {
+        self.scratch = Some(core::mem::take(scratch));
+        let item = self.parse();
+        *scratch = self.scratch.take().unwrap_or_default();
+        item
+    }
 
-        if buffer.len() != 6 {
-            return Err(Error::MissingData);
+    /// like `parse`, but when the reader runs out of bytes in the middle of the document the items
+    /// read so far are returned together with the error, see `Error::is_incomplete`. the lists and
+    /// maps that were cut off hold the items that were complete. other errors fail like in
+    /// `parse`, that includes blobs with corrupt compressed data
+    pub fn parse_lenient(&mut self) -> Result<(Option<Item>, Option<Error>), Error> {
+        let mut builder = TreeBuilder::new(self.reject_duplicate_keys);
+        let result = self.at_offset(|parser| {
+            parser.parse_header()?;
+            parser.visit_item(&mut builder)
+        });
+        match result {
+            Ok(_) => Ok((builder.finish(), None)),
+            Err(e) if e.is_incomplete() => Ok((builder.finish_partial(), Some(e))),
+            Err(e) => Err(e),
         }
+    }
 
-        if buffer[0..4] != consts::PREFIX {
-            return Err(Error::InvalidHeader);
+    /// like `parse`, but the document has to be all that is left in the reader. the rest is read
+    /// to count it for `Error::TrailingData`, offset at the end of the document
+    pub fn parse_complete(&mut self) -> Result<Option<Item>, Error> {
+        let item = self.parse()?;
+        let end = self.bytes_consumed();
+        let trailing = self.skip_to_end()?;
+        if trailing > 0 {
+            return Err(Error::At {
+                offset: end,
+                source: Box::new(Error::TrailingData(trailing)),
+            });
         }
-        let version = u16::from_le_bytes(buffer[4..6].try_into().unwrap());
-        self.version = Some(version);
+        Ok(item)
+    }
+
+    /// the type of the next item as named in `Error::TypeMismatch`, without consuming it. `None`
+    /// at the end of the data. the header is parsed first, the next `parse` continues after it
+    pub fn peek_type(&mut self) -> Result<Option<&'static str>, Error> {
+        self.at_offset(|parser| {
+            if !parser.header_read {
+                parser.parse_header()?;
+                parser.header_read = true;
+            }
+            Ok(parser.peek_byte()?.map(type_name))
+        })
+    }
+
+    /// parses the document and converts it with `FromItem`, an empty document is `None`
+    pub fn parse_typed<T: FromItem>(&mut self) -> Result<Option<T>, Error> {
+        self.parse()?.map(T::from_item).transpose()
+    }
+
+    /// parses a document with a map at the root into `M`, for example a `BTreeMap` to get the
+    /// keys sorted. only the root map is an `M`, its values are regular items and an `Item` always
+    /// holds a `Map`, so maps nested below the root are `Item::Map` whatever `M` is
+    pub fn parse_into<M: MapLike>(&mut self) -> Result<Option<M>, Error> {
+        self.at_offset(|parser| {
+            parser.parse_header()?;
+
+            match parser.peek_byte()? {
+                None => Ok(None),
+                Some(byte) if value_type(byte) == b'm' => {
+                    let byte = parser.next()?;
+                    parser.resolve_extension(byte)?;
+                    parser.nested(Self::parse_map_into).map(Some)
+                }
+                Some(byte) => Err(Error::TypeMismatch {
+                    expected: "map",
+                    found: type_name(byte),
+                }),
+            }
+        })
+    }
+
+    /// parses a document with a list of integers at the root straight into a `Vec`, without an
+    /// `Item` for every element. `Int16` elements are widened, any other element fails with
+    /// `Error::TypeMismatch`
+    pub fn parse_i64_array(&mut self) -> Result<Option<Vec<i64>>, Error> {
+        self.parse_array("int64", |parser, byte| match byte {
+            b'i' => parser.parse_int64().map(Some),
+            b'h' => parser.parse_int16().map(|n| Some(i64::from(n))),
+            _ => Ok(None),
+        })
+    }
+
+    /// like `parse_i64_array` for a list of floats, `F32` elements are widened
+    pub fn parse_f64_array(&mut self) -> Result<Option<Vec<f64>>, Error> {
+        self.parse_array("f64", |parser, byte| match byte {
+            b'd' => parser.parse_f64().map(Some),
+            b'f' => parser.parse_f32().map(|v| Some(f64::from(v))),
+            consts::TYPE_F16 => parser.parse_f16().map(|v| Some(f64::from(v))),
+            _ => Ok(None),
+        })
+    }
+
+    /// parses documents that were written back to back, each with its own header, until the
+    /// reader is exhausted
+    pub fn parse_all(&mut self) -> Result<Vec<Item>, Error> {
+        self.at_offset(|parser| {
+            let mut items = Vec::new();
+            while parser.reader.peek().is_some() {
+                parser.parse_header()?;
+                items.push(parser.parse_item()?.ok_or(Error::MissingData)?);
+            }
+            Ok(items)
+        })
+    }
+
+    /// parses the header and walks the document, calling `visitor` for every value instead of
+    /// building the `Item` tree
+    pub fn parse_with<V: Visitor>(&mut self, visitor: &mut V) -> Result<(), Error> {
+        self.at_offset(|parser| {
+            parser.parse_header()?;
+            parser.visit_item(visitor)?;
+            Ok(())
+        })
+    }
+
+    /// parses the header and the start of a root list, the elements are then parsed one at a
+    /// time by the returned iterator. `Limits::max_bytes` applies to each element
+    pub fn into_list_iter(mut self) -> Result<ListIter<R>, Error> {
+        let remaining = self.at_offset(|parser| {
+            parser.parse_header()?;
+
+            let byte = parser.next()?;
+            match parser.resolve_extension(byte)? {
+                b'l' => parser.parse_collection_size(),
+                byte => Err(Error::TypeMismatch {
+                    expected: "list",
+                    found: type_name(byte),
+                }),
+            }
+        })?;
+
+        Ok(ListIter {
+            parser: self,
+            remaining,
+        })
+    }
+
+    /// parses the header and the start of a root map, the entries are then parsed one at a time
+    /// by the returned iterator. `Limits::max_bytes` applies to each entry
+    pub fn into_map_iter(mut self) -> Result<MapIter<R>, Error> {
+        let remaining = self.at_offset(|parser| {
+            parser.parse_header()?;
+
+            let byte = parser.next()?;
+            match parser.resolve_extension(byte)? {
+                b'm' => parser.parse_collection_size(),
+                byte => Err(Error::TypeMismatch {
+                    expected: "map",
+                    found: type_name(byte),
+                }),
+            }
+        })?;
+
+        Ok(MapIter {
+            parser: self,
+            remaining,
+        })
+    }
 
+    /// runs `f`, errors get the offset where they happened attached
+    fn at_offset<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        f(self).map_err(|source| Error::At {
+            offset: self.reader.position(),
+            source: Box::new(source),
+        })
+    }
+
+    /// runs `f` one level deeper into the document
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth == self.limits.max_depth {
+            return Err(Error::DepthExceeded);
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// accounts for `bytes` more allocated memory in the current document
+    fn allocate(&mut self, bytes: usize) -> Result<(), Error> {
+        self.allocated = self
+            .allocated
+            .checked_add(bytes)
+            .filter(|allocated| *allocated <= self.limits.max_bytes)
+            .ok_or(Error::LimitExceeded)?;
         Ok(())
     }
 
+    fn allocate_collection(&mut self, length: usize) -> Result<(), Error> {
+        if length > self.limits.max_length {
+            return Err(Error::LimitExceeded);
+        }
+        self.allocate(length.saturating_mul(core::mem::size_of::<Item>()))
+    }
+
+    /// the root list of a document as `T`s, `element` parses the value after a type byte and gives
+    /// `None` for a type that is not `expected`
+    fn parse_array<T>(
+        &mut self,
+        expected: &'static str,
+        mut element: impl FnMut(&mut Self, u8) -> Result<Option<T>, Error>,
+    ) -> Result<Option<Vec<T>>, Error> {
+        self.at_offset(|parser| {
+            parser.parse_header()?;
+            match parser.peek_byte()? {
+                None => return Ok(None),
+                Some(byte) if value_type(byte) == b'l' => {
+                    let byte = parser.next()?;
+                    parser.resolve_extension(byte)?
+                }
+                Some(byte) => {
+                    return Err(Error::TypeMismatch {
+                        expected: "list",
+                        found: type_name(byte),
+                    })
+                }
+            };
+
+            let length = parser.parse_collection_size()?;
+            if let Some(length) = length {
+                if length > parser.limits.max_length {
+                    return Err(Error::LimitExceeded);
+                }
+                parser.allocate(length.saturating_mul(core::mem::size_of::<T>()))?;
+            }
+
+            let mut values = Vec::with_capacity(length.unwrap_or(0).min(MAX_PREALLOCATE));
+            while Some(values.len()) != length {
+                let byte = match parser.peek_byte()? {
+                    Some(byte) => byte,
+                    None if length.is_none() => break,
+                    None => return Err(Error::MissingData),
+                };
+                if length.is_none() {
+                    if values.len() == parser.limits.max_length {
+                        return Err(Error::LimitExceeded);
+                    }
+                    parser.allocate(core::mem::size_of::<T>())?;
+                }
+                parser.next()?;
+                let value = element(parser, byte)?.ok_or(Error::TypeMismatch {
+                    expected,
+                    found: type_name(byte),
+                })?;
+                values.push(value);
+            }
+            Ok(Some(values))
+        })
+    }
+
+    fn peek_byte(&mut self) -> Result<Option<u8>, Error> {
+        match self.reader.peek() {
+            None => Ok(None),
+            Some(Ok(byte)) => Ok(Some(*byte)),
+            // take the error out of the reader
+            Some(Err(_)) => self.next().map(Some),
+        }
+    }
+
+    fn parse_header(&mut self) -> Result<(), Error> {
+        // `peek_type` already read it
+        if core::mem::take(&mut self.header_read) {
+            return Ok(());
+        }
+        self.allocated = 0;
+
+        let mut buffer = [0; 6];
+        if self.reader.read_into(&mut buffer)? != buffer.len() {
+            return Err(Error::MissingData);
+        }
+
+        let version = header_version(&buffer)?;
+        self.version = Some(version);
+        check_version(version)
+    }
+
     fn parse_item(&mut self) -> Result<Option<Item>, Error> {
+        let mut builder = TreeBuilder::new(self.reject_duplicate_keys);
+        self.visit_item(&mut builder)?;
+        Ok(builder.finish())
+    }
+
+    /// walks the next item, `false` if there is none
+    fn visit_item<V: Visitor>(&mut self, visitor: &mut V) -> Result<bool, Error> {
         let next_byte = match self.reader.next() {
-            None => return Ok(None),
-            Some(Err(e)) => return Err(Error::Reader(e)),
+            None => return Ok(false),
+            Some(Err(e)) => return Err(Error::from(e)),
             Some(Ok(byte)) => byte,
         };
-
-        let item = match next_byte {
-            b'v' => Item::Void,
-            b'n' => Item::Bool(false),
-            b'y' => Item::Bool(true),
-            b'h' => self.parse_int16().map(Item::Int16)?,
-            b'i' => self.parse_int64().map(Item::Int64)?,
-            b'f' => self.parse_f32().map(Item::F32)?,
-            b'd' => self.parse_f64().map(Item::F64)?,
-            b's' => self.parse_string().map(Item::String)?,
-            b'l' => self.parse_list().map(Item::List)?,
-            b'm' => self.parse_map().map(Item::Map)?,
-            b'b' => self.parse_blob().map(Item::Blob)?,
-            _ => return Ok(None),
+        let next_byte = self.resolve_extension(next_byte)?;
+
+        match next_byte {
+            b'v' => visitor.on_void()?,
+            b'n' => visitor.on_bool(false)?,
+            b'y' => visitor.on_bool(true)?,
+            b'h' if self.widen_integers => visitor.on_int64(self.parse_int16()?.into())?,
+            b'h' => visitor.on_int16(self.parse_int16()?)?,
+            b'i' => visitor.on_int64(self.parse_int64()?)?,
+            b'f' => visitor.on_f32(self.parse_f32()?)?,
+            consts::TYPE_F16 => visitor.on_f32(self.parse_f16()?)?,
+            b'd' => visitor.on_f64(self.parse_f64()?)?,
+            b's' => match self.parse_string() {
+                Err(Error::InvalidUtf8(data)) if self.invalid_utf8_as_blob => {
+                    visitor.on_blob(data)?
+                }
+                text => visitor.on_string(text?)?,
+            },
+            b'l' => self.nested(|parser| parser.visit_list(visitor))?,
+            b'm' => self.nested(|parser| parser.visit_map(visitor))?,
+            b'b' if self.keep_blob_compression => {
+                visitor.on_compressed_blob(self.parse_compressed_blob()?)?
+            }
+            b'b' => visitor.on_blob(self.parse_blob()?)?,
+            byte => return Err(Error::InvalidType(byte)),
         };
 
-        Ok(Some(item))
+        Ok(true)
     }
 
     #[inline]
     fn next(&mut self) -> Result<u8, Error> {
-        let byte = self.reader.next().ok_or_else(|| Error::Eof)??;
+        let byte = self.reader.next().ok_or(Error::Eof)??;
         Ok(byte)
     }
 
-    fn parse_int16(&mut self) -> Result<i16, Error> {
-        let first_byte = self.next()?;
-        let second_byte = self.next()?;
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut buffer = [0; N];
+        self.read_exact(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        if self.reader.read_into(buffer)? != buffer.len() {
+            return Err(Error::Eof);
+        }
+        Ok(())
+    }
 
-        Ok(i16::from_le_bytes([first_byte, second_byte]))
+    /// the bytes of a number in little endian order, whatever `endianness` is set to
+    fn read_number<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut buffer = self.read_array()?;
+        if self.endian == Endian::Big {
+            buffer.reverse();
+        }
+        Ok(buffer)
+    }
+
+    fn parse_int16(&mut self) -> Result<i16, Error> {
+        Ok(i16::from_le_bytes(self.read_number()?))
     }
 
     fn parse_int64(&mut self) -> Result<i64, Error> {
-        let byte_1 = self.next()?;
-        let byte_2 = self.next()?;
-        let byte_3 = self.next()?;
-        let byte_4 = self.next()?;
-        let byte_5 = self.next()?;
-        let byte_6 = self.next()?;
-        let byte_7 = self.next()?;
-        let byte_8 = self.next()?;
-
-        Ok(i64::from_le_bytes([
-            byte_1, byte_2, byte_3, byte_4, byte_5, byte_6, byte_7, byte_8,
-        ]))
+        Ok(i64::from_le_bytes(self.read_number()?))
     }
 
+    /// sizes are written as a u64, also where `usize` is smaller
     fn parse_usize(&mut self) -> Result<usize, Error> {
-        let byte_1 = self.next()?;
-        let byte_2 = self.next()?;
-        let byte_3 = self.next()?;
-        let byte_4 = self.next()?;
-        let byte_5 = self.next()?;
-        let byte_6 = self.next()?;
-        let byte_7 = self.next()?;
-        let byte_8 = self.next()?;
-
-        Ok(usize::from_le_bytes([
-            byte_1, byte_2, byte_3, byte_4, byte_5, byte_6, byte_7, byte_8,
-        ]))
+        usize::try_from(u64::from_le_bytes(self.read_number()?)).map_err(|_| Error::LimitExceeded)
     }
 
     fn parse_f32(&mut self) -> Result<f32, Error> {
-        let byte_1 = self.next()?;
-        let byte_2 = self.next()?;
-        let byte_3 = self.next()?;
-        let byte_4 = self.next()?;
+        Ok(f32::from_le_bytes(self.read_number()?))
+    }
 
-        Ok(f32::from_le_bytes([byte_1, byte_2, byte_3, byte_4]))
+    /// the half precision floats of `consts::TYPE_F16`, which is not in the spec. widened because
+    /// there is no `Item` for it
+    fn parse_f16(&mut self) -> Result<f32, Error> {
+        f16_to_f32(self.read_number()?)
     }
 
     fn parse_f64(&mut self) -> Result<f64, Error> {
-        let byte_1 = self.next()?;
-        let byte_2 = self.next()?;
-        let byte_3 = self.next()?;
-        let byte_4 = self.next()?;
-        let byte_5 = self.next()?;
-        let byte_6 = self.next()?;
-        let byte_7 = self.next()?;
-        let byte_8 = self.next()?;
-
-        Ok(f64::from_le_bytes([
-            byte_1, byte_2, byte_3, byte_4, byte_5, byte_6, byte_7, byte_8,
-        ]))
+        Ok(f64::from_le_bytes(self.read_number()?))
     }
 
     fn parse_string(&mut self) -> Result<String, Error> {
         let length = self.parse_size()?;
+        self.allocate(length)?;
+
+        let utf8_lossy = self.utf8_lossy;
+        self.read_payload(
+            length,
+            Checksum::Unchecked,
+            |text_data| match String::from_utf8(text_data.into_owned()) {
+                Ok(text) => Ok(text),
+                Err(e) if utf8_lossy => Ok(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+                Err(e) => Err(Error::InvalidUtf8(e.into_bytes())),
+            },
+        )
+    }
 
-        let text_data: Result<Vec<u8>, _> = self.reader.by_ref().take(length).collect();
-        return String::from_utf8(text_data?).map_err(|_| Error::InvalidUtf8);
+    /// a type byte marked as an extension is followed by the name of the extension, this reads
+    /// the name and gives the type byte of the value. other type bytes are returned as they are
+    fn resolve_extension(&mut self, byte: u8) -> Result<u8, Error> {
+        if !is_extension(byte) {
+            return Ok(byte);
+        }
+        let name = self.parse_string()?;
+        extension_type(byte, name)
     }
 
     fn parse_size(&mut self) -> Result<usize, Error> {
         let first_byte = self.next()?;
+        self.parse_size_from(first_byte)
+    }
 
-        match first_byte {
-            consts::LARGE_SIZE => Ok(self.parse_usize()?),
-            consts::SMALL_SIZE_CUTOFF..=u8::MAX => Err(Error::InvalidSize),
-            l => Ok(l as usize),
+    fn parse_size_from(&mut self, first_byte: u8) -> Result<usize, Error> {
+        match consts::decode_small_size(first_byte)? {
+            Some(size) => Ok(size),
+            None => self.parse_usize(),
         }
     }
 
-    fn parse_map(&mut self) -> Result<Map, Error> {
-        let length = self.parse_size()?;
-        let mut map = Map::with_capacity(length);
+    /// like `parse_size` but also understands streamed collections, `None` means the
+    /// collection runs until the end of the data
+    fn parse_collection_size(&mut self) -> Result<Option<usize>, Error> {
+        match self.next()? {
+            consts::STREAM_CLOSED => Ok(Some(self.parse_usize()?)),
+            consts::STREAM_OPEN => {
+                // an open stream has not written its final length yet
+                self.parse_usize()?;
+                Ok(None)
+            }
+            first_byte => Ok(Some(self.parse_size_from(first_byte)?)),
+        }
+    }
+
+    fn visit_map<V: Visitor>(&mut self, visitor: &mut V) -> Result<(), Error> {
+        let length = self.parse_collection_size()?;
+        visitor.begin_map(length)?;
+
+        match length {
+            Some(length) => {
+                self.allocate_collection(length)?;
+
+                for _ in 0..length {
+                    visitor.map_key(self.parse_string()?)?;
+                    if !self.visit_item(visitor)? {
+                        return Err(Error::MissingData);
+                    }
+                }
+            }
+            None => {
+                let mut length = 0;
+                while self.reader.peek().is_some() {
+                    if length == self.limits.max_length {
+                        return Err(Error::LimitExceeded);
+                    }
+                    length += 1;
+                    self.allocate(core::mem::size_of::<Item>())?;
+
+                    visitor.map_key(self.parse_string()?)?;
+                    if !self.visit_item(visitor)? {
+                        return Err(Error::MissingData);
+                    }
+                }
+            }
+        }
+
+        visitor.end_map()
+    }
+
+    fn parse_map_into<M: MapLike>(&mut self) -> Result<M, Error> {
+        let length = self.parse_collection_size()?;
+        if let Some(length) = length {
+            self.allocate_collection(length)?;
+        }
+        let mut map = M::default();
+
+        let mut read = 0;
+        loop {
+            match length {
+                Some(length) if read == length => break,
+                None if self.reader.peek().is_none() => break,
+                None if read == self.limits.max_length => return Err(Error::LimitExceeded),
+                None => self.allocate(core::mem::size_of::<Item>())?,
+                Some(_) => (),
+            }
+            read += 1;
 
-        for _ in 0..length {
             let key = self.parse_string()?;
-            let item = self.parse_item()?.ok_or_else(|| Error::MissingData)?;
+            let item = self.parse_item()?.ok_or(Error::MissingData)?;
+            if self.reject_duplicate_keys && map.contains_key(&key) {
+                return Err(Error::DuplicateKey(key));
+            }
             map.insert(key, item);
         }
 
         Ok(map)
     }
 
-    fn parse_list(&mut self) -> Result<Vec<Item>, Error> {
-        let length = self.parse_size()?;
-        let mut list = Vec::with_capacity(length);
+    fn visit_list<V: Visitor>(&mut self, visitor: &mut V) -> Result<(), Error> {
+        let length = self.parse_collection_size()?;
+        visitor.begin_list(length)?;
+
+        match length {
+            Some(length) => {
+                self.allocate_collection(length)?;
 
-        for _ in 0..length {
-            list.push(self.parse_item()?.ok_or_else(|| Error::MissingData)?);
+                for _ in 0..length {
+                    if !self.visit_item(visitor)? {
+                        return Err(Error::MissingData);
+                    }
+                }
+            }
+            None => {
+                let mut length = 0;
+                while self.visit_item(visitor)? {
+                    if length == self.limits.max_length {
+                        return Err(Error::LimitExceeded);
+                    }
+                    length += 1;
+                    self.allocate(core::mem::size_of::<Item>())?;
+                }
+            }
         }
 
-        Ok(list)
+        visitor.end_list()
     }
 
-    fn parse_blob(&mut self) -> Result<Vec<u8>, Error> {
+    /// parses a document with a blob at the root, without decompressing or verifying it.
+    /// the returned data is exactly the `used_size` bytes stored in the stream
+    pub fn parse_blob_raw(&mut self) -> Result<(BlobMeta, Vec<u8>), Error> {
+        self.at_offset(|parser| {
+            parser.parse_header()?;
+
+            match parser.next()? {
+                b'b' => parser.parse_blob_parts(),
+                byte => Err(Error::TypeMismatch {
+                    expected: "blob",
+                    found: type_name(byte),
+                }),
+            }
+        })
+    }
+
+    /// reads the blob header, leaving the reader at the start of the data
+    fn parse_blob_meta(&mut self) -> Result<BlobMeta, Error> {
         let allocated_size = self.parse_size()?;
         let used_size = self.parse_size()?;
         let data_size = self.parse_size()?;
+        if used_size > allocated_size {
+            return Err(Error::InvalidBlob);
+        }
         let compressed_setting = self.next()?;
         let checksum_setting = self.next()?;
-        let md5_hash = if checksum_setting == consts::CHECKSUM_SET {
-            let hash: Result<Vec<u8>, _> = self.reader.by_ref().take(16).collect();
-            hash?
-        } else {
-            Vec::new()
+        let checksum = match blob::checksum_len(checksum_setting) {
+            // a short hash is a truncated stream, not a wrong checksum
+            Some(length) => Some(self.read_bytes(length)?),
+            None => None,
         };
         let byte_alignment_indicator = self.next()?;
 
         self.skip_bytes(byte_alignment_indicator as usize);
 
-        let mut data = Vec::with_capacity(used_size);
-        for b in self.reader.by_ref().take(used_size) {
-            data.push(b?)
+        Ok(BlobMeta {
+            allocated_size,
+            used_size,
+            data_size,
+            compression: compressed_setting,
+            checksum_setting,
+            checksum,
+            alignment: byte_alignment_indicator,
+        })
+    }
+
+    /// accounts for the stored data and the decompressed data of a blob
+    fn allocate_blob(&mut self, meta: &BlobMeta) -> Result<(), Error> {
+        self.allocate(meta.used_size)?;
+        if meta.compression != consts::COMPRESSION_NOT_SET {
+            self.allocate(meta.data_size)?;
         }
+        Ok(())
+    }
 
-        self.skip_bytes(allocated_size.saturating_sub(used_size) as usize);
+    fn parse_blob_parts(&mut self) -> Result<(BlobMeta, Vec<u8>), Error> {
+        let meta = self.parse_blob_meta()?;
+        self.allocate_blob(&meta)?;
+        let data = self.read_bytes(meta.used_size)?;
 
-        if !Self::check_hash(&data, &md5_hash) {
-            return Err(Error::InvalidBlobHash);
-        }
+        self.skip_bytes(meta.allocated_size - meta.used_size);
+
+        Ok((meta, data))
+    }
+
+    fn parse_blob(&mut self) -> Result<Vec<u8>, Error> {
+        self.parse_compressed_blob().map(|blob| blob.data)
+    }
 
-        let data = match compressed_setting {
-            consts::COMPRESSION_NOT_SET => data,
-            consts::COMPRESSION_ZLIB => Self::decompress_zlib(&data, data_size)?,
-            consts::COMPRESSION_BZ2 => Self::decompress_bz2(&data, data_size)?,
-            _ => {
-                return Err(Error::from(InvalidExtension::InvalidCompressionSetting(
-                    compressed_setting,
-                )))
+    fn parse_compressed_blob(&mut self) -> Result<Blob, Error> {
+        let meta = self.parse_blob_meta()?;
+        self.allocate_blob(&meta)?;
+
+        let checksum = Checksum::new(&meta, self.verify_checksums);
+        let data = self.read_payload(meta.used_size, checksum, |data| {
+            Ok(blob::decompress(&meta, data)?.into_owned())
+        })?;
+        self.skip_bytes(meta.allocated_size - meta.used_size);
+
+        let compression = Compression::try_from(meta.compression)?;
+        Ok(Blob::new(data, compression).with_spare(meta.allocated_size - meta.used_size))
+    }
+
+    /// reads `n` bytes into the scratch buffer if there is one, into a new buffer otherwise. the
+    /// checksum is computed while reading and checked before `f` gets the bytes
+    fn read_payload<T>(
+        &mut self,
+        n: usize,
+        mut checksum: Checksum,
+        f: impl FnOnce(Cow<[u8]>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        match self.scratch.take() {
+            Some(mut scratch) => {
+                let result = self
+                    .read_bytes_into(n, &mut scratch, &mut checksum)
+                    .and_then(|()| checksum.finish())
+                    .and_then(|()| f(Cow::Borrowed(&scratch)));
+                self.scratch = Some(scratch);
+                result
             }
-        };
+            None => {
+                let mut data = Vec::new();
+                self.read_bytes_into(n, &mut data, &mut checksum)?;
+                checksum.finish()?;
+                f(Cow::Owned(data))
+            }
+        }
+    }
 
+    fn read_bytes(&mut self, n: usize) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::new();
+        self.read_bytes_into(n, &mut data, &mut Checksum::Unchecked)?;
         Ok(data)
     }
 
-    #[cfg(feature = "md5")]
-    fn check_hash(data: &[u8], hash: &[u8]) -> bool {
-        md5::compute(data).as_slice() == hash
+    /// reads `n` bytes into `data`, replacing what it held but keeping its capacity
+    fn read_bytes_into(
+        &mut self,
+        n: usize,
+        data: &mut Vec<u8>,
+        checksum: &mut Checksum,
+    ) -> Result<(), Error> {
+        data.clear();
+        while data.len() < n {
+            // the declared size isn't trusted, the buffer at most doubles per read
+            let start = data.len();
+            let chunk = (n - start).min(start.max(MAX_PREALLOCATE));
+            data.resize(start + chunk, 0);
+            self.read_exact(&mut data[start..])?;
+            checksum.update(&data[start..]);
+        }
+        Ok(())
     }
 
-    #[cfg(not(feature = "md5"))]
-    fn check_hash(_: &[u8], _: &[u8]) -> bool {
-        true
+    /// skips padding, running out of data here is left for the next read to report
+    fn skip_bytes(&mut self, n: usize) {
+        let _ = self.skip_exact(n);
     }
 
-    #[cfg(feature = "zlib")]
-    fn decompress_zlib(data: &[u8], size: usize) -> Result<Vec<u8>, Error> {
-        let mut decompressor = flate2::read::ZlibDecoder::new(&data[..]);
-        let mut buffer = Vec::with_capacity(size);
-        decompressor.read_to_end(&mut buffer)?;
-        Ok(buffer)
+    /// reads the reader to the end, returns how many bytes that were
+    fn skip_to_end(&mut self) -> Result<usize, Error> {
+        let mut buffer = [0; 256];
+        let mut skipped = 0;
+        loop {
+            match self.reader.read_into(&mut buffer)? {
+                0 => return Ok(skipped),
+                n => skipped += n,
+            }
+        }
     }
 
-    #[cfg(not(feature = "zlib"))]
-    fn decompress_zlib(_: &[u8], _: usize) -> Result<Vec<u8>, Error> {
-        Err(Error::from(InvalidExtension::ZlibNotCompiled))
+    fn skip_exact(&mut self, n: usize) -> Result<(), Error> {
+        let mut buffer = [0; 256];
+        let mut remaining = n;
+        while remaining > 0 {
+            let chunk = remaining.min(buffer.len());
+            self.read_exact(&mut buffer[..chunk])?;
+            remaining -= chunk;
+        }
+        Ok(())
     }
 
-    #[cfg(feature = "bz2")]
-    fn decompress_bz2(data: &[u8], size: usize) -> Result<Vec<u8>, Error> {
-        let mut decompressor = bzip2::read::BzDecoder::new(&data[..]);
-        let mut buffer = Vec::with_capacity(size);
-        decompressor.read_to_end(&mut buffer)?;
-        Ok(buffer)
+    /// steps over the next value like `visit_item` but without reading it into memory, `false`
+    /// if there is none. blobs are neither verified nor decompressed
+    fn skip_value(&mut self) -> Result<bool, Error> {
+        let next_byte = match self.reader.next() {
+            None => return Ok(false),
+            Some(Err(e)) => return Err(Error::from(e)),
+            Some(Ok(byte)) => byte,
+        };
+        // the value after the name of an extension is a regular value, whatever the extension is
+        let next_byte = match is_extension(next_byte) {
+            true => {
+                let length = self.parse_size()?;
+                self.skip_exact(length)?;
+                next_byte.to_ascii_lowercase()
+            }
+            false => next_byte,
+        };
+
+        match next_byte {
+            b'v' | b'n' | b'y' => {}
+            b'h' => self.skip_exact(2)?,
+            b'f' => self.skip_exact(4)?,
+            consts::TYPE_F16 => self.skip_exact(2)?,
+            b'i' | b'd' => self.skip_exact(8)?,
+            b's' => {
+                let length = self.parse_size()?;
+                self.skip_exact(length)?;
+            }
+            b'l' => self.nested(Self::skip_list)?,
+            b'm' => self.nested(Self::skip_map)?,
+            b'b' => {
+                let meta = self.parse_blob_meta()?;
+                self.skip_exact(meta.allocated_size)?;
+            }
+            byte => return Err(Error::InvalidType(byte)),
+        }
+
+        Ok(true)
     }
 
-    #[cfg(not(feature = "bz2"))]
-    fn decompress_bz2(_: &[u8], _: usize) -> Result<Vec<u8>, Error> {
-        Err(Error::from(InvalidExtension::Bz2NotCompiled))
+    fn skip_list(&mut self) -> Result<(), Error> {
+        match self.parse_collection_size()? {
+            Some(length) => {
+                for _ in 0..length {
+                    if !self.skip_value()? {
+                        return Err(Error::MissingData);
+                    }
+                }
+            }
+            None => while self.skip_value()? {},
+        }
+        Ok(())
     }
 
-    fn skip_bytes(&mut self, n: usize) {
-        self.reader.by_ref().take(n).for_each(|_| {});
+    fn skip_map(&mut self) -> Result<(), Error> {
+        let length = self.parse_collection_size()?;
+        let mut read = 0;
+        while match length {
+            Some(length) => read < length,
+            None => self.reader.peek().is_some(),
+        } {
+            read += 1;
+            let key_length = self.parse_size()?;
+            self.skip_exact(key_length)?;
+            if !self.skip_value()? {
+                return Err(Error::MissingData);
+            }
+        }
+        Ok(())
     }
 }
 
-#[test]
-fn parses_empty() {
-    let data = b"";
-
-    let mut parser = Parser::new(Box::new(data.as_slice()));
+impl<'a> Parser<&'a [u8]> {
+    /// parser for data that is in memory already. only a shorthand for `Parser::new` with a slice,
+    /// it reads the same way and has no faster path. `parse_borrowed` can borrow from the slice
+    pub fn from_bytes(data: &'a [u8]) -> Parser<&'a [u8]> {
+        Parser::new(data)
+    }
 
-    assert_eq!(parser.parse(), Err(Error::MissingData));
-}
+    /// parses without copying strings and uncompressed blobs, they borrow from the slice instead
+    pub fn parse_borrowed(&mut self) -> Result<Option<BorrowedItem<'a>>, Error> {
+        self.at_offset(|parser| {
+            parser.parse_header()?;
+            parser.parse_borrowed_item()
+        })
+    }
 
-#[test]
-fn parses_version() {
-    let data = b"BSDF\x04\x02";
+    fn parse_borrowed_item(&mut self) -> Result<Option<BorrowedItem<'a>>, Error> {
+        let next_byte = match self.reader.next() {
+            None => return Ok(None),
+            Some(Err(e)) => return Err(Error::from(e)),
+            Some(Ok(byte)) => byte,
+        };
+        let next_byte = self.resolve_extension(next_byte)?;
 
-    let mut parser = Parser::new(Box::new(data.as_slice()));
+        let item = match next_byte {
+            b'v' => BorrowedItem::Void,
+            b'n' => BorrowedItem::Bool(false),
+            b'y' => BorrowedItem::Bool(true),
+            b'h' if self.widen_integers => {
+                self.parse_int16().map(|n| BorrowedItem::Int64(n.into()))?
+            }
+            b'h' => self.parse_int16().map(BorrowedItem::Int16)?,
+            b'i' => self.parse_int64().map(BorrowedItem::Int64)?,
+            b'f' => self.parse_f32().map(BorrowedItem::F32)?,
+            consts::TYPE_F16 => self.parse_f16().map(BorrowedItem::F32)?,
+            b'd' => self.parse_f64().map(BorrowedItem::F64)?,
+            b's' => match self.parse_borrowed_string() {
+                Err(Error::InvalidUtf8(data)) if self.invalid_utf8_as_blob => {
+                    BorrowedItem::Blob(Cow::Owned(data))
+                }
+                text => BorrowedItem::String(text?),
+            },
+            b'l' => self
+                .nested(Self::parse_borrowed_list)
+                .map(BorrowedItem::List)?,
+            b'm' => self
+                .nested(Self::parse_borrowed_map)
+                .map(BorrowedItem::Map)?,
+            b'b' => self.parse_borrowed_blob().map(BorrowedItem::Blob)?,
+            byte => return Err(Error::InvalidType(byte)),
+        };
 
-    parser.parse().unwrap();
+        Ok(Some(item))
+    }
 
-    assert_eq!(parser.version, Some(516));
-}
+    fn parse_borrowed_string(&mut self) -> Result<Cow<'a, str>, Error> {
+        let length = self.parse_size()?;
 
-#[test]
-fn parse_float64() {
-    // copied from python
-    let data = b"BSDF\x02\x02do\x12\x83\xc0\xca!\t@";
+        let text_data = self.reader.take_slice(length).ok_or(Error::Eof)?;
+        match core::str::from_utf8(text_data) {
+            Ok(text) => Ok(Cow::Borrowed(text)),
+            Err(_) if self.utf8_lossy => Ok(String::from_utf8_lossy(text_data)),
+            Err(_) => Err(Error::InvalidUtf8(text_data.to_vec())),
+        }
+    }
 
-    let mut parser = Parser::new(Box::new(data.as_slice()));
+    fn parse_borrowed_map(&mut self) -> Result<BorrowedMap<'a>, Error> {
+        let length = self.parse_collection_size()?;
+        if let Some(length) = length {
+            self.allocate_collection(length)?;
+        }
+        let mut map = item::map_with_capacity(length.unwrap_or(0).min(MAX_PREALLOCATE));
+
+        // entries read so far, duplicate keys don't grow the map
+        let mut read = 0;
+        loop {
+            match length {
+                Some(length) if read == length => break,
+                None if self.reader.peek().is_none() => break,
+                None if read == self.limits.max_length => return Err(Error::LimitExceeded),
+                None => self.allocate(core::mem::size_of::<BorrowedItem>())?,
+                Some(_) => (),
+            }
+            read += 1;
 
-    let item = parser.parse().unwrap();
+            let key = self.parse_borrowed_string()?;
+            let item = self.parse_borrowed_item()?.ok_or(Error::MissingData)?;
+            if self.reject_duplicate_keys && map.contains_key(&key) {
+                return Err(Error::DuplicateKey(key.into_owned()));
+            }
+            map.insert(key, item);
+        }
 
-    assert_eq!(item, Some(Item::F64(3.1415)));
-}
+        Ok(map)
+    }
 
-#[test]
-fn parses_string() {
-    // copied from python
-    let data = b"BSDF\x02\x02s\xfd\xc9\x02\x00\x00\x00\x00\x00\x00\nLorem ipsum dolor sit amet, consectetur adipiscing elit. Duis id ante velit. Aenean euismod, ipsum a varius finibus, eros erat tincidunt ligula, non malesuada ex ipsum et tellus. Cras id convallis mauris, mattis porttitor nulla. In urna orci, faucibus ut consequat eleifend, vulputate ac elit. Integer gravida porta arcu, id volutpat libero lobortis at. Aenean bibendum eleifend auctor. Sed lectus purus, aliquet non purus ut, feugiat tristique leo. Praesent ut leo blandit, vulputate ex sit amet, venenatis libero. Curabitur vehicula ut enim sed posuere. Aliquam nec elit fringilla, aliquet lectus sed, suscipit quam. Vivamus malesuada ligula eu luctus finibus. Proin euismod sem sit amet eros euismod rhoncus.\n";
+    fn parse_borrowed_list(&mut self) -> Result<Vec<BorrowedItem<'a>>, Error> {
+        let length = self.parse_collection_size()?;
+        if let Some(length) = length {
+            self.allocate_collection(length)?;
+        }
+        let mut list = Vec::with_capacity(length.unwrap_or(0).min(MAX_PREALLOCATE));
+
+        loop {
+            let item = match length {
+                Some(length) if list.len() == length => break,
+                Some(_) => self.parse_borrowed_item()?.ok_or(Error::MissingData)?,
+                None => match self.parse_borrowed_item()? {
+                    None => break,
+                    Some(_) if list.len() == self.limits.max_length => {
+                        return Err(Error::LimitExceeded)
+                    }
+                    Some(item) => {
+                        self.allocate(core::mem::size_of::<BorrowedItem>())?;
+                        item
+                    }
+                },
+            };
+            list.push(item);
+        }
 
-    let expected = Item::String(String::from("\nLorem ipsum dolor sit amet, consectetur adipiscing elit. Duis id ante velit. Aenean euismod, ipsum a varius finibus, eros erat tincidunt ligula, non malesuada ex ipsum et tellus. Cras id convallis mauris, mattis porttitor nulla. In urna orci, faucibus ut consequat eleifend, vulputate ac elit. Integer gravida porta arcu, id volutpat libero lobortis at. Aenean bibendum eleifend auctor. Sed lectus purus, aliquet non purus ut, feugiat tristique leo. Praesent ut leo blandit, vulputate ex sit amet, venenatis libero. Curabitur vehicula ut enim sed posuere. Aliquam nec elit fringilla, aliquet lectus sed, suscipit quam. Vivamus malesuada ligula eu luctus finibus. Proin euismod sem sit amet eros euismod rhoncus.\n"));
+        Ok(list)
+    }
 
-    let mut parser = Parser::new(Box::new(data.as_slice()));
+    fn parse_borrowed_blob(&mut self) -> Result<Cow<'a, [u8]>, Error> {
+        let meta = self.parse_blob_meta()?;
+        self.allocate_blob(&meta)?;
+        let data = self.reader.take_slice(meta.used_size).ok_or(Error::Eof)?;
 
-    let item = parser.parse().unwrap();
+        self.skip_bytes(meta.allocated_size - meta.used_size);
 
-    assert_eq!(item, Some(expected));
+        blob::unpack(&meta, Cow::Borrowed(data), self.verify_checksums)
+    }
 }
 
-#[test]
-fn parses_normal_map() {
-    // copied from python
-    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+pub struct ListIter<R: Read> {
+    parser: Parser<R>,
+    // `None` for open streamed lists, which end with the data
+    remaining: Option<usize>,
+}
 
-    let expected = Item::Map(Map::from_iter([
-        (String::from("test"), Item::Int16(1)),
-        (String::from("test1"), Item::Int16(2)),
-        (String::from("test3"), Item::Int16(4)),
-    ]));
+impl<R: Read> Iterator for ListIter<R> {
+    type Item = Result<Item, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // every element is handed out on its own, so each gets the whole byte budget
+        self.parser.allocated = 0;
+        let item = match self.remaining {
+            Some(0) => return None,
+            Some(_) => self
+                .parser
+                .at_offset(|parser| parser.parse_item()?.ok_or(Error::MissingData)),
+            None => self.parser.at_offset(Parser::parse_item).transpose()?,
+        };
 
-    let mut parser = Parser::new(Box::new(data.as_slice()));
+        // a broken stream can't be recovered, so stop after the first error
+        self.remaining = match item {
+            Ok(_) => self.remaining.map(|remaining| remaining - 1),
+            Err(_) => Some(0),
+        };
+        Some(item)
+    }
 
-    let item = parser.parse().unwrap();
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.remaining)
+    }
+}
 
-    assert_eq!(item, Some(expected));
+pub struct MapIter<R: Read> {
+    parser: Parser<R>,
+    // `None` for open streamed maps, which end with the data
+    remaining: Option<usize>,
 }
 
-#[test]
-fn parses_nested_map() {
-    // copied from python
+impl<R: Read> MapIter<R> {
+    /// reads the key of the next entry and steps over its value without building it, like
+    /// `Parser::skip_item` does for a whole document. `None` after the last entry
+    pub fn skip_value(&mut self) -> Option<Result<String, Error>> {
+        self.advance(|parser| {
+            let key = parser.parse_string()?;
+            if !parser.skip_value()? {
+                return Err(Error::MissingData);
+            }
+            Ok(key)
+        })
+    }
+
+    fn entry(parser: &mut Parser<R>) -> Result<(String, Item), Error> {
+        let key = parser.parse_string()?;
+        let value = parser.parse_item()?.ok_or(Error::MissingData)?;
+        Ok((key, value))
+    }
+
+    fn advance<T>(
+        &mut self,
+        f: impl FnOnce(&mut Parser<R>) -> Result<T, Error>,
+    ) -> Option<Result<T, Error>> {
+        // like `ListIter`, every entry gets the whole byte budget
+        self.parser.allocated = 0;
+        let entry = match self.remaining {
+            Some(0) => return None,
+            Some(_) => self.parser.at_offset(f),
+            None => match self.parser.at_offset(Parser::peek_byte) {
+                Ok(None) => return None,
+                Ok(Some(_)) => self.parser.at_offset(f),
+                Err(e) => Err(e),
+            },
+        };
+
+        // a broken stream can't be recovered, so stop after the first error
+        self.remaining = match entry {
+            Ok(_) => self.remaining.map(|remaining| remaining - 1),
+            Err(_) => Some(0),
+        };
+        Some(entry)
+    }
+}
+
+impl<R: Read> Iterator for MapIter<R> {
+    type Item = Result<(String, Item), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.advance(Self::entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.remaining)
+    }
+}
+
+/// the version from the six header bytes
+fn header_version(buffer: &[u8]) -> Result<u16, Error> {
+    if buffer[0..4] != consts::PREFIX {
+        return Err(Error::InvalidHeader);
+    }
+    Ok(u16::from_le_bytes([buffer[4], buffer[5]]))
+}
+
+fn check_version(version: u16) -> Result<(), Error> {
+    // newer minor versions are expected to stay readable
+    let [major, minor] = version.to_le_bytes();
+    if major > consts::MAX_SUPPORTED_MAJOR {
+        return Err(Error::UnsupportedVersion { major, minor });
+    }
+    Ok(())
+}
+
+#[cfg(feature = "half")]
+fn f16_to_f32(bytes: [u8; 2]) -> Result<f32, Error> {
+    Ok(half::f16::from_le_bytes(bytes).to_f32())
+}
+
+#[cfg(not(feature = "half"))]
+fn f16_to_f32(_: [u8; 2]) -> Result<f32, Error> {
+    Err(Error::from(crate::InvalidExtension::HalfNotCompiled))
+}
+
+/// the spec marks a value encoded by an extension with the uppercase of its type byte. the spec
+/// has no standard type bytes besides these, everything else it knows is an extension
+fn is_extension(byte: u8) -> bool {
+    byte.is_ascii_uppercase()
+        && matches!(
+            byte.to_ascii_lowercase(),
+            b'v' | b'n' | b'y' | b'h' | b'i' | b'f' | b'd' | b's' | b'l' | b'm' | b'b'
+        )
+}
+
+/// the type byte of the value of an extension. only known extensions are read, others fail with
+/// `InvalidExtension::Unsupported` because their value means something else than it says
+fn extension_type(byte: u8, name: String) -> Result<u8, Error> {
+    let byte = byte.to_ascii_lowercase();
+    match (name.as_str(), byte) {
+        #[cfg(feature = "ndarray")]
+        (item::ndarray::EXTENSION, b'm') => Ok(byte),
+        _ => Err(Error::from(crate::InvalidExtension::Unsupported(name))),
+    }
+}
+
+/// the type of the value after `byte`, for an extension that is the lowercase of its type byte
+fn value_type(byte: u8) -> u8 {
+    match is_extension(byte) {
+        true => byte.to_ascii_lowercase(),
+        false => byte,
+    }
+}
+
+fn type_name(byte: u8) -> &'static str {
+    match value_type(byte) {
+        b'v' => "void",
+        b'n' | b'y' => "bool",
+        b'h' => "int16",
+        b'i' => "int64",
+        b'f' | consts::TYPE_F16 => "f32",
+        b'd' => "f64",
+        b's' => "string",
+        b'l' => "list",
+        b'm' => "map",
+        b'b' => "blob",
+        _ => "unknown",
+    }
+}
+
+#[test]
+fn parses_empty() {
+    let data = b"";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    assert_eq!(
+        parser.parse().map_err(Error::into_inner),
+        Err(Error::MissingData)
+    );
+}
+
+#[test]
+fn parses_version() {
+    let data = b"BSDF\x02\x04";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    parser.parse().unwrap();
+
+    assert_eq!(parser.version, Some(1026));
+}
+
+#[test]
+fn parses_version_major_minor() {
+    let data = b"BSDF\x04\x02";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    assert_eq!(parser.version(), None);
+
+    assert_eq!(
+        parser.parse().map_err(Error::into_inner),
+        Err(Error::UnsupportedVersion { major: 4, minor: 2 })
+    );
+
+    assert_eq!(parser.version(), Some((4, 2)));
+}
+
+#[test]
+fn parses_unsupported_version() {
+    let data = b"BSDF\x63\x00";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    assert_eq!(
+        parser.parse().map_err(Error::into_inner),
+        Err(Error::UnsupportedVersion {
+            major: 99,
+            minor: 0
+        })
+    );
+}
+
+#[test]
+#[allow(clippy::approx_constant)]
+fn parse_float64() {
+    // copied from python
+    let data = b"BSDF\x02\x02do\x12\x83\xc0\xca!\t@";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse().unwrap();
+
+    assert_eq!(item, Some(Item::F64(3.1415)));
+}
+
+#[test]
+#[allow(clippy::approx_constant)]
+fn parse_endianness() {
+    // copied from python
+    let data = b"BSDF\x02\x02do\x12\x83\xc0\xca!\t@";
+
+    let item = Parser::new(data.as_slice())
+        .endianness(Endian::Big)
+        .parse()
+        .unwrap();
+    assert_ne!(item, Some(Item::F64(3.1415)));
+    let swapped = f64::from_be_bytes(*b"o\x12\x83\xc0\xca!\t@");
+    assert_eq!(item, Some(Item::F64(swapped)));
+
+    let item = Parser::new(data.as_slice())
+        .endianness(Endian::Little)
+        .parse()
+        .unwrap();
+    assert_eq!(item, Some(Item::F64(3.1415)));
+
+    // sizes are swapped too
+    let data = b"BSDF\x02\x02l\xfd\x00\x00\x00\x00\x00\x00\x00\x01h\x00\x05";
+    assert_eq!(
+        Parser::new(data.as_slice()).endianness(Endian::Big).parse(),
+        Ok(Some(Item::List(vec![Item::Int16(5)])))
+    );
+}
+
+#[test]
+fn parses_string() {
+    // copied from python
+    let data = b"BSDF\x02\x02s\xfd\xc9\x02\x00\x00\x00\x00\x00\x00\nLorem ipsum dolor sit amet, consectetur adipiscing elit. Duis id ante velit. Aenean euismod, ipsum a varius finibus, eros erat tincidunt ligula, non malesuada ex ipsum et tellus. Cras id convallis mauris, mattis porttitor nulla. In urna orci, faucibus ut consequat eleifend, vulputate ac elit. Integer gravida porta arcu, id volutpat libero lobortis at. Aenean bibendum eleifend auctor. Sed lectus purus, aliquet non purus ut, feugiat tristique leo. Praesent ut leo blandit, vulputate ex sit amet, venenatis libero. Curabitur vehicula ut enim sed posuere. Aliquam nec elit fringilla, aliquet lectus sed, suscipit quam. Vivamus malesuada ligula eu luctus finibus. Proin euismod sem sit amet eros euismod rhoncus.\n";
+
+    let expected = Item::String(String::from("\nLorem ipsum dolor sit amet, consectetur adipiscing elit. Duis id ante velit. Aenean euismod, ipsum a varius finibus, eros erat tincidunt ligula, non malesuada ex ipsum et tellus. Cras id convallis mauris, mattis porttitor nulla. In urna orci, faucibus ut consequat eleifend, vulputate ac elit. Integer gravida porta arcu, id volutpat libero lobortis at. Aenean bibendum eleifend auctor. Sed lectus purus, aliquet non purus ut, feugiat tristique leo. Praesent ut leo blandit, vulputate ex sit amet, venenatis libero. Curabitur vehicula ut enim sed posuere. Aliquam nec elit fringilla, aliquet lectus sed, suscipit quam. Vivamus malesuada ligula eu luctus finibus. Proin euismod sem sit amet eros euismod rhoncus.\n"));
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse().unwrap();
+
+    assert_eq!(item, Some(expected));
+}
+
+#[test]
+fn parser_invalid_utf8() {
+    let data = b"BSDF\x02\x02s\x02h\xff";
+
+    let error = Parser::new(data.as_slice()).parse().unwrap_err();
+    assert_eq!(error.into_inner(), Error::InvalidUtf8(vec![b'h', 0xff]));
+
+    let error = Parser::new(data.as_slice()).parse_borrowed().unwrap_err();
+    assert_eq!(error.into_inner(), Error::InvalidUtf8(vec![b'h', 0xff]));
+}
+
+#[test]
+fn parser_invalid_utf8_as_blob() {
+    let data = b"BSDF\x02\x02l\x02s\x02h\xffs\x02hi";
+
+    let mut parser = Parser::new(data.as_slice()).invalid_utf8_as_blob(true);
+    assert_eq!(
+        parser.parse().unwrap(),
+        Some(Item::list([
+            Item::Blob(vec![b'h', 0xff]),
+            Item::String(String::from("hi"))
+        ]))
+    );
+
+    let mut parser = Parser::new(data.as_slice()).invalid_utf8_as_blob(true);
+    assert_eq!(
+        parser.parse_borrowed().unwrap(),
+        Some(BorrowedItem::List(vec![
+            BorrowedItem::Blob(Cow::Owned(vec![b'h', 0xff])),
+            BorrowedItem::String(Cow::Borrowed("hi"))
+        ]))
+    );
+}
+
+#[test]
+fn parser_utf8_lossy() {
+    let data = b"BSDF\x02\x02m\x01\x02k\xffs\x02h\xff";
+
+    let expected = Item::map([(
+        String::from("k\u{fffd}"),
+        Item::String(String::from("h\u{fffd}")),
+    )]);
+
+    let mut parser = Parser::new(data.as_slice()).utf8_lossy(true);
+    assert_eq!(parser.parse().unwrap(), Some(expected.clone()));
+
+    let mut parser = Parser::new(data.as_slice()).utf8_lossy(true);
+    let item = parser
+        .parse_borrowed()
+        .unwrap()
+        .map(BorrowedItem::into_owned);
+    assert_eq!(item, Some(expected));
+}
+
+#[test]
+fn parser_peek_type() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+
+    let expected = Item::map([
+        (String::from("test"), Item::Int16(1)),
+        (String::from("test1"), Item::Int16(2)),
+        (String::from("test3"), Item::Int16(4)),
+    ]);
+
+    let mut parser = Parser::new(data.as_slice());
+
+    assert_eq!(parser.peek_type().unwrap(), Some("map"));
+    assert_eq!(parser.peek_type().unwrap(), Some("map"));
+    assert_eq!(parser.parse().unwrap(), Some(expected));
+}
+
+#[test]
+fn parser_peek_type_empty() {
+    let data = b"BSDF\x02\x02";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    assert_eq!(parser.peek_type().unwrap(), None);
+    assert_eq!(parser.parse().unwrap(), None);
+}
+
+#[test]
+fn parser_reset() {
+    // copied from python
+    let map = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+    let string = b"BSDF\x02\x01s\x04text";
+
+    let mut parser = Parser::new(map.as_slice());
+    assert_eq!(parser.peek_type().unwrap(), Some("map"));
+    assert_eq!(parser.version(), Some((2, 2)));
+
+    parser.reset(string.as_slice());
+    assert_eq!(parser.version(), None);
+    assert_eq!(parser.parse().unwrap(), Some(Item::from("text")));
+    assert_eq!(parser.version(), Some((2, 1)));
+
+    parser.reset(map.as_slice());
+    assert!(parser.parse().unwrap().unwrap().as_map().is_some());
+    assert_eq!(parser.version(), Some((2, 2)));
+}
+
+#[test]
+fn parser_invalid_type() {
+    let data = b"BSDF\x02\x02m\x02\x01ah\x01\x00\x01bx";
+
+    assert_eq!(
+        Parser::new(data.as_slice())
+            .parse()
+            .map_err(Error::into_inner),
+        Err(Error::InvalidType(b'x'))
+    );
+    assert_eq!(
+        Parser::new(data.as_slice())
+            .parse_borrowed()
+            .map_err(Error::into_inner),
+        Err(Error::InvalidType(b'x'))
+    );
+}
+
+#[test]
+fn parser_extension() {
+    // a complex number as the reference implementation writes it: the `c` extension encodes
+    // `1+2j` as a list of two floats, marked with an uppercase `L`
+    let data =
+        b"BSDF\x02\x02L\x01c\x02d\x00\x00\x00\x00\x00\x00\xf0?d\x00\x00\x00\x00\x00\x00\x00@";
+    let unsupported = || Error::from(crate::InvalidExtension::Unsupported(String::from("c")));
+
+    let error = Parser::new(data.as_slice()).parse().unwrap_err();
+    // the offset is after the name, where the value would start
+    assert_eq!(error.offset(), Some(9));
+    assert_eq!(error.into_inner(), unsupported());
+    // skipping doesn't need to know the extension
+    let concatenated = [data.as_slice(), b"BSDF\x02\x02v"].concat();
+    let mut parser = Parser::new(concatenated.as_slice());
+    assert_eq!(parser.skip_item(), Ok(()));
+    assert_eq!(parser.parse(), Ok(Some(Item::Void)));
+    assert_eq!(
+        Parser::from_bytes(data)
+            .parse_borrowed()
+            .map_err(Error::into_inner),
+        Err(unsupported())
+    );
+
+    // an ndarray inside a list, the extension marks the map. it is only known with the feature
+    let data = b"BSDF\x02\x02l\x01M\x07ndarray\x00";
+    #[cfg(not(feature = "ndarray"))]
+    assert_eq!(
+        Parser::new(data.as_slice())
+            .parse()
+            .map_err(Error::into_inner),
+        Err(Error::from(crate::InvalidExtension::Unsupported(
+            String::from("ndarray")
+        )))
+    );
+    #[cfg(feature = "ndarray")]
+    assert_eq!(
+        Parser::new(data.as_slice()).parse(),
+        Ok(Some(Item::list([Item::Map(Map::new())])))
+    );
+
+    // uppercase bytes that are no type stay invalid
+    assert_eq!(
+        Parser::new(b"BSDF\x02\x02X".as_slice())
+            .parse()
+            .map_err(Error::into_inner),
+        Err(Error::InvalidType(b'X'))
+    );
+}
+
+#[test]
+fn parser_lenient() {
+    // a list of three int16 cut off after the second
+    let data = b"BSDF\x02\x02l\x03h\x01\x00h\x02\x00";
+
+    let (item, error) = Parser::new(data.as_slice()).parse_lenient().unwrap();
+    assert_eq!(item, Some(Item::list([Item::Int16(1), Item::Int16(2)])));
+    let error = error.unwrap();
+    assert_eq!(error.offset(), Some(data.len()));
+    assert!(error.is_incomplete());
+    assert!(Parser::new(data.as_slice()).parse().is_err());
+
+    // cut off inside a value of a nested map, the key of that value is dropped
+    let data = b"BSDF\x02\x02l\x02m\x02\x01ay\x01bh\x01";
+    let (item, _) = Parser::new(data.as_slice()).parse_lenient().unwrap();
+    assert_eq!(
+        item,
+        Some(Item::list([Item::map([(
+            String::from("a"),
+            Item::Bool(true)
+        )])]))
+    );
+
+    let data = b"BSDF\x02\x02h\x01\x00";
+    assert_eq!(
+        Parser::new(data.as_slice()).parse_lenient(),
+        Ok((Some(Item::Int16(1)), None))
+    );
+    let data = b"BSDF\x02\x02l\x02h\x01\x00x";
+    assert_eq!(
+        Parser::new(data.as_slice())
+            .parse_lenient()
+            .map_err(Error::into_inner),
+        Err(Error::InvalidType(b'x'))
+    );
+}
+
+#[test]
+fn parser_empty_key() {
+    let data = b"BSDF\x02\x02m\x01\x00h\x01\x00";
+
+    let item = Parser::new(data.as_slice()).parse().unwrap().unwrap();
+    assert_eq!(item[""], Item::Int16(1));
+
+    let item = Parser::new(data.as_slice())
+        .parse_borrowed()
+        .unwrap()
+        .unwrap();
+    assert_eq!(item.into_owned()[""], Item::Int16(1));
+}
+
+#[test]
+fn parser_long_key() {
+    let key = "k".repeat(300);
+    let mut data = b"BSDF\x02\x02m\x01\xfd".to_vec();
+    data.extend_from_slice(&300u64.to_le_bytes());
+    data.extend_from_slice(key.as_bytes());
+    data.extend_from_slice(b"h\x01\x00");
+
+    let item = Parser::new(data.as_slice()).parse().unwrap().unwrap();
+    assert_eq!(item[key.as_str()], Item::Int16(1));
+
+    let item = Parser::new(data.as_slice())
+        .parse_borrowed()
+        .unwrap()
+        .unwrap();
+    assert_eq!(item.into_owned()[key.as_str()], Item::Int16(1));
+}
+
+#[test]
+fn parser_size_boundaries() {
+    // 250 is the largest size that fits in the size byte
+    let mut data = b"BSDF\x02\x02s\xfa".to_vec();
+    data.extend_from_slice(&[b'x'; 250]);
+    assert_eq!(
+        Parser::new(data.as_slice()).parse(),
+        Ok(Some(Item::String("x".repeat(250))))
+    );
+
+    // 253 is followed by a u64, even for sizes that would fit in a byte
+    let mut data = b"BSDF\x02\x02s\xfd".to_vec();
+    data.extend_from_slice(&3u64.to_le_bytes());
+    data.extend_from_slice(b"abc");
+    assert_eq!(
+        Parser::new(data.as_slice()).parse(),
+        Ok(Some(Item::from("abc")))
+    );
+
+    // all eight size bytes are read, the value after the string starts at the right offset
+    let mut data = b"BSDF\x02\x02l\x02s\xfd".to_vec();
+    data.extend_from_slice(&3u64.to_le_bytes());
+    data.extend_from_slice(b"abch\x01\x00");
+    assert_eq!(
+        Parser::new(data.as_slice()).parse(),
+        Ok(Some(Item::list([Item::from("abc"), Item::Int16(1)])))
+    );
+
+    // 251 and 252 are reserved, 254 and 255 only mark streams for lists and maps
+    for byte in [251, 252, 254, 255] {
+        let data = [b'B', b'S', b'D', b'F', 2, 2, b's', byte];
+        assert_eq!(
+            Parser::new(data.as_slice())
+                .parse()
+                .map_err(Error::into_inner),
+            Err(Error::InvalidSize)
+        );
+    }
+}
+
+#[test]
+fn parser_i64_array() {
+    let data = b"BSDF\x02\x02l\x03i\x01\x00\x00\x00\x00\x00\x00\x00i\x02\x00\x00\x00\x00\x00\x00\x00i\xff\xff\xff\xff\xff\xff\xff\xff";
+
+    assert_eq!(
+        Parser::new(data.as_slice()).parse_i64_array(),
+        Ok(Some(vec![1, 2, -1]))
+    );
+
+    // a mix of widths, in an open stream
+    let data = b"BSDF\x02\x02l\xff\x00\x00\x00\x00\x00\x00\x00\x00h\x05\x00i\x06\x00\x00\x00\x00\x00\x00\x00";
+    assert_eq!(
+        Parser::new(data.as_slice()).parse_i64_array(),
+        Ok(Some(vec![5, 6]))
+    );
+}
+
+#[test]
+fn parser_typed_array_mismatch() {
+    let data = b"BSDF\x02\x02l\x02h\x01\x00s\x01a";
+    assert_eq!(
+        Parser::new(data.as_slice())
+            .parse_i64_array()
+            .map_err(Error::into_inner),
+        Err(Error::TypeMismatch {
+            expected: "int64",
+            found: "string"
+        })
+    );
+
+    let data = b"BSDF\x02\x02l\x02d\x00\x00\x00\x00\x00\x00\xf8?f\x00\x00\xc0?";
+    assert_eq!(
+        Parser::new(data.as_slice()).parse_f64_array(),
+        Ok(Some(vec![1.5, 1.5]))
+    );
+    assert_eq!(
+        Parser::new(b"BSDF\x02\x02h\x01\x00".as_slice())
+            .parse_f64_array()
+            .map_err(Error::into_inner),
+        Err(Error::TypeMismatch {
+            expected: "list",
+            found: "int16"
+        })
+    );
+}
+
+#[test]
+fn parser_complete() {
+    assert_eq!(
+        Parser::new(b"BSDF\x02\x02y".as_slice()).parse_complete(),
+        Ok(Some(Item::Bool(true)))
+    );
+    assert_eq!(
+        Parser::new(b"BSDF\x02\x02yx".as_slice()).parse_complete(),
+        Err(Error::At {
+            offset: 7,
+            source: Box::new(Error::TrailingData(1))
+        })
+    );
+    // parse itself does not look past the document
+    assert_eq!(
+        Parser::new(b"BSDF\x02\x02yx".as_slice()).parse(),
+        Ok(Some(Item::Bool(true)))
+    );
+}
+
+#[test]
+fn parser_widen_integers() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+
+    let item = Parser::new(data.as_slice())
+        .widen_integers(true)
+        .parse()
+        .unwrap()
+        .unwrap();
+    let map = item.as_map().unwrap();
+    assert_eq!(map.len(), 3);
+    assert!(map.values().all(|value| value.as_int64().is_some()));
+    assert_eq!(item["test3"], Item::Int64(4));
+
+    let item = Parser::new(data.as_slice())
+        .widen_integers(true)
+        .parse_borrowed()
+        .unwrap()
+        .unwrap();
+    assert_eq!(item.into_owned()["test1"], Item::Int64(2));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parser_typed() {
+    use std::collections::HashMap;
+
+    // copied from python
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+
+    let map = Parser::new(data.as_slice())
+        .parse_typed::<HashMap<String, i16>>()
+        .unwrap()
+        .unwrap();
+    let expected = HashMap::from([
+        (String::from("test"), 1),
+        (String::from("test1"), 2),
+        (String::from("test3"), 4),
+    ]);
+    assert_eq!(map, expected);
+
+    assert_eq!(
+        Parser::new(data.as_slice()).parse_typed::<Vec<i16>>(),
+        Err(Error::TypeMismatch {
+            expected: "list",
+            found: "map"
+        })
+    );
+}
+
+#[test]
+fn parser_scratch_test() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    let expected = Parser::new(data.as_slice()).parse().unwrap();
+
+    let mut scratch = Vec::new();
+    let item = Parser::new(data.as_slice()).parse_with_scratch(&mut scratch);
+    assert_eq!(item, Ok(expected.clone()));
+    let capacity = scratch.capacity();
+    assert!(capacity >= "some text".len());
+
+    let item = Parser::new(data.as_slice()).parse_with_scratch(&mut scratch);
+    assert_eq!(item, Ok(expected));
+    assert_eq!(scratch.capacity(), capacity);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parser_scratch_blob_test() {
+    let mut buffer = Vec::new();
+    let item = Item::list([Item::Blob(vec![1, 2, 3]), Item::from("abc")]);
+    crate::Encoder::new(&mut buffer).write(&item).unwrap();
+
+    let mut scratch = Vec::new();
+    let mut parser = Parser::new(buffer.as_slice());
+    assert_eq!(parser.parse_with_scratch(&mut scratch), Ok(Some(item)));
+    // the parser keeps working without it afterwards
+    parser.reset(b"BSDF\x02\x02s\x02hi".as_slice());
+    assert_eq!(parser.parse(), Ok(Some(Item::from("hi"))));
+}
+
+#[test]
+fn parser_map_iter() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+
+    let entries = Parser::new(data.as_slice())
+        .into_map_iter()
+        .unwrap()
+        .collect::<Result<Vec<_>, Error>>()
+        .unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            (String::from("test"), Item::Int16(1)),
+            (String::from("test1"), Item::Int16(2)),
+            (String::from("test3"), Item::Int16(4)),
+        ]
+    );
+
+    assert!(matches!(
+        Parser::new(b"BSDF\x02\x02l\x00".as_slice()).into_map_iter(),
+        Err(Error::At { .. })
+    ));
+}
+
+#[test]
+fn parser_map_iter_limits() {
+    // ten entries with 20 byte strings are more than the budget together, but not one by one
+    let mut data = b"BSDF\x02\x02m\x0a".to_vec();
+    for key in b'a'..b'k' {
+        data.extend_from_slice(&[1, key, b's', 20]);
+        data.extend_from_slice(&[b'x'; 20]);
+    }
+    let limits = Limits {
+        max_bytes: 100,
+        ..Limits::default()
+    };
+
+    let entries = Parser::with_limits(data.as_slice(), limits)
+        .into_map_iter()
+        .unwrap()
+        .collect::<Result<Vec<_>, Error>>()
+        .unwrap();
+    assert_eq!(entries.len(), 10);
+    assert_eq!(entries[9], (String::from("j"), Item::from("x".repeat(20))));
+
+    let mut entries = Parser::with_limits(data.as_slice(), limits)
+        .into_map_iter()
+        .unwrap();
+    for key in b'a'..b'k' {
+        assert_eq!(entries.skip_value(), Some(Ok(String::from(key as char))));
+    }
+    assert_eq!(entries.skip_value(), None);
+}
+
+#[test]
+fn parser_map_iter_truncated() {
+    let data = b"BSDF\x02\x02m\x02\x01ah\x01\x00\x01b";
+
+    let mut entries = Parser::new(data.as_slice()).into_map_iter().unwrap();
+    assert_eq!(
+        entries.next(),
+        Some(Ok((String::from("a"), Item::Int16(1))))
+    );
+    assert_eq!(
+        entries.next().map(|entry| entry.map_err(Error::into_inner)),
+        Some(Err(Error::MissingData))
+    );
+    assert_eq!(entries.next(), None);
+}
+
+#[test]
+fn parser_empty_body() {
+    let data = b"BSDF\x02\x02";
+
+    assert_eq!(Parser::new(data.as_slice()).parse(), Ok(None));
+    assert_eq!(Parser::new(data.as_slice()).parse_borrowed(), Ok(None));
+}
+
+#[test]
+fn parser_junk_body() {
+    let data = b"BSDF\x02\x02\x00junk";
+
+    let error = Parser::new(data.as_slice()).parse().unwrap_err();
+    assert_eq!(error.offset(), Some(7));
+    assert_eq!(error.into_inner(), Error::InvalidType(0));
+    assert_eq!(
+        Parser::new(data.as_slice())
+            .parse_borrowed()
+            .map_err(Error::into_inner),
+        Err(Error::InvalidType(0))
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parser_reads_in_bulk() {
+    // the number of `read` calls stands in for a benchmark, the byte at a time path made one
+    // call per byte
+    struct CountingReader<'a> {
+        data: &'a [u8],
+        calls: usize,
+    }
+
+    impl std::io::Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.calls += 1;
+            self.data.read(buf)
+        }
+    }
+
+    let text = "bsdf ".repeat(100_000);
+    let mut data = b"BSDF\x02\x02s\xfd".to_vec();
+    data.extend_from_slice(&(text.len() as u64).to_le_bytes());
+    data.extend_from_slice(text.as_bytes());
+
+    let mut reader = CountingReader {
+        data: &data,
+        calls: 0,
+    };
+    let mut parser = Parser::new(&mut reader);
+    assert_eq!(parser.parse().unwrap(), Some(Item::String(text)));
+    assert_eq!(parser.reader.position(), data.len());
+    drop(parser);
+
+    assert!(reader.calls < 20, "{} read calls", reader.calls);
+}
+
+#[test]
+fn parser_duplicate_keys() {
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x04testh\x04\x00";
+
+    let expected = Item::map([
+        (String::from("test"), Item::Int16(4)),
+        (String::from("test1"), Item::Int16(2)),
+    ]);
+
+    assert_eq!(
+        Parser::new(data.as_slice()).parse().unwrap(),
+        Some(expected.clone())
+    );
+    assert_eq!(
+        Parser::new(data.as_slice())
+            .parse_borrowed()
+            .unwrap()
+            .map(BorrowedItem::into_owned),
+        Some(expected)
+    );
+
+    let mut parser = Parser::new(data.as_slice()).reject_duplicate_keys(true);
+    assert_eq!(
+        parser.parse().map_err(Error::into_inner),
+        Err(Error::DuplicateKey(String::from("test")))
+    );
+
+    let mut parser = Parser::new(data.as_slice()).reject_duplicate_keys(true);
+    assert_eq!(
+        parser.parse_borrowed().map_err(Error::into_inner),
+        Err(Error::DuplicateKey(String::from("test")))
+    );
+}
+
+#[test]
+fn parser_skip_item() {
+    // copied from python
+    let nested = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+    // zlib compressed, skipping doesn't need the zlib feature
+    let blob = b"BSDF\x02\x02b\x0a\x0a\x10\x01\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09";
+    let map = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+    let data = [nested.as_slice(), blob, map].concat();
+
+    let mut parser = Parser::new(data.as_slice());
+    assert_eq!(parser.peek_type().unwrap(), Some("map"));
+    parser.skip_item().unwrap();
+    parser.skip_item().unwrap();
+
+    let expected = Item::map([
+        (String::from("test"), Item::Int16(1)),
+        (String::from("test1"), Item::Int16(2)),
+        (String::from("test3"), Item::Int16(4)),
+    ]);
+    assert_eq!(parser.parse().unwrap(), Some(expected));
+    assert_eq!(
+        parser.peek_type().map_err(Error::into_inner),
+        Err(Error::MissingData)
+    );
+}
+
+#[test]
+fn parser_map_iter_skip_value() {
+    // the nested map fixture with a key after `nested`
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text\x05afterh\x02\x00";
+
+    let mut entries = Parser::new(data.as_slice()).into_map_iter().unwrap();
+    assert_eq!(
+        entries.next(),
+        Some(Ok((String::from("test"), Item::Int16(1))))
+    );
+    assert_eq!(entries.skip_value(), Some(Ok(String::from("nested"))));
+    assert_eq!(
+        entries.next(),
+        Some(Ok((String::from("after"), Item::Int16(2))))
+    );
+    assert_eq!(entries.skip_value(), None);
+}
+
+#[test]
+fn parser_skip_item_truncated() {
+    let data = b"BSDF\x02\x02l\x02s\x04text";
+
+    let mut parser = Parser::new(data.as_slice());
+    assert_eq!(
+        parser.skip_item().map_err(Error::into_inner),
+        Err(Error::MissingData)
+    );
+
+    let data = b"BSDF\x02\x02s\x04te";
+
+    let mut parser = Parser::new(data.as_slice());
+    assert_eq!(
+        parser.skip_item().map_err(Error::into_inner),
+        Err(Error::Eof)
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parser_parse_into() {
+    use alloc::collections::BTreeMap;
+    use std::collections::HashMap;
+
+    // copied from python
+    let data = b"BSDF\x02\x02m\x03\x05test3h\x04\x00\x04testh\x01\x00\x05test1h\x02\x00";
+
+    let hash_map: HashMap<String, Item> =
+        Parser::new(data.as_slice()).parse_into().unwrap().unwrap();
+    let btree_map: BTreeMap<String, Item> =
+        Parser::new(data.as_slice()).parse_into().unwrap().unwrap();
+
+    assert_eq!(hash_map.len(), 3);
+    assert_eq!(hash_map["test1"], Item::Int16(2));
+    assert_eq!(
+        btree_map.into_iter().collect::<Vec<_>>(),
+        vec![
+            (String::from("test"), Item::Int16(1)),
+            (String::from("test1"), Item::Int16(2)),
+            (String::from("test3"), Item::Int16(4)),
+        ]
+    );
+
+    let data = b"BSDF\x02\x02h\x01\x00";
+    assert_eq!(
+        Parser::new(data.as_slice())
+            .parse_into::<HashMap<String, Item>>()
+            .map_err(Error::into_inner),
+        Err(Error::TypeMismatch {
+            expected: "map",
+            found: "int16"
+        })
+    );
+
+    // only the root is a `BTreeMap`
+    let data = b"BSDF\x02\x02m\x01\x01am\x01\x01bh\x01\x00";
+    let btree_map: BTreeMap<String, Item> =
+        Parser::new(data.as_slice()).parse_into().unwrap().unwrap();
+    assert_eq!(
+        btree_map["a"],
+        Item::map([(String::from("b"), Item::Int16(1))])
+    );
+    assert!(btree_map["a"].as_map().is_some());
+}
+
+#[test]
+#[allow(clippy::approx_constant)]
+fn parser_from_bytes() {
+    // copied from python
+    let map = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+    let float = b"BSDF\x02\x02do\x12\x83\xc0\xca!\t@";
+    let blob = b"BSDF\x02\x02b\n\n\n\x00\x00\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00";
+
+    let expected = Item::map([
+        (String::from("test"), Item::Int16(1)),
+        (String::from("test1"), Item::Int16(2)),
+        (String::from("test3"), Item::Int16(4)),
+    ]);
+    assert_eq!(Parser::from_bytes(map).parse().unwrap(), Some(expected));
+    assert_eq!(
+        Parser::from_bytes(float).parse().unwrap(),
+        Some(Item::F64(3.1415))
+    );
+    assert_eq!(
+        Parser::from_bytes(blob).parse_borrowed().unwrap(),
+        Some(BorrowedItem::Blob(Cow::Borrowed(&[
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 0
+        ])))
+    );
+    assert_eq!(
+        Parser::from_bytes(&map[..12])
+            .parse()
+            .map_err(Error::into_inner),
+        Err(Error::Eof)
+    );
+}
+
+#[test]
+fn parses_all_documents() {
+    let document = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+    let data = [document.as_slice(), document.as_slice()].concat();
+
+    let expected = Item::map([
+        (String::from("test"), Item::Int16(1)),
+        (String::from("test1"), Item::Int16(2)),
+        (String::from("test3"), Item::Int16(4)),
+    ]);
+
+    let mut parser = Parser::new(data.as_slice());
+    assert_eq!(parser.parse_all(), Ok(vec![expected.clone(), expected]));
+
+    let mut parser = Parser::new([].as_slice());
+    assert_eq!(parser.parse_all(), Ok(vec![]));
+}
+
+#[test]
+fn parses_all_truncated_header() {
+    let data = b"BSDF\x02\x02vBSD";
+
+    let mut parser = Parser::new(data.as_slice());
+    assert_eq!(
+        parser.parse_all(),
+        Err(Error::At {
+            offset: 10,
+            source: Box::new(Error::MissingData)
+        })
+    );
+}
+
+#[test]
+fn parses_normal_map() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+
+    let expected = Item::Map(Map::from_iter([
+        (String::from("test"), Item::Int16(1)),
+        (String::from("test1"), Item::Int16(2)),
+        (String::from("test3"), Item::Int16(4)),
+    ]));
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse().unwrap();
+
+    assert_eq!(item, Some(expected));
+}
+
+#[test]
+fn parses_nested_map() {
+    // copied from python
     let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
 
     let expected = Item::Map(Map::from_iter([
@@ -348,13 +2190,61 @@ fn parses_nested_map() {
         ),
     ]));
 
-    let mut parser = Parser::new(Box::new(data.as_slice()));
+    let mut parser = Parser::new(data.as_slice());
 
     let item = parser.parse().unwrap();
 
     assert_eq!(item, Some(expected));
 }
 
+#[cfg(feature = "std")]
+#[test]
+fn parser_reader_errors() {
+    use std::io::{ErrorKind, Read};
+
+    struct Failing(ErrorKind);
+
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(self.0.into())
+        }
+    }
+
+    let data = b"BSDF\x02\x02s\x05ab";
+
+    assert_eq!(
+        Parser::new(data.chain(Failing(ErrorKind::UnexpectedEof)))
+            .parse()
+            .map_err(Error::into_inner),
+        Err(Error::Eof)
+    );
+    assert_eq!(
+        Parser::new(data.chain(Failing(ErrorKind::BrokenPipe)))
+            .parse()
+            .map_err(Error::into_inner),
+        Err(Error::Reader(ErrorKind::BrokenPipe.into()))
+    );
+}
+
+#[test]
+fn parser_bytes_consumed() {
+    // copied from python
+    let data = b"BSDF\x02\x02b\n\n\n\x00\xff\x7fc\xcbm\x06yr\xc3\xf3O\tK\xb7\xe7v\xa8\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00";
+
+    let mut parser = Parser::new(data.as_slice()).verify_checksums(false);
+    assert_eq!(parser.bytes_consumed(), 0);
+    parser.parse().unwrap();
+    assert_eq!(parser.bytes_consumed(), data.len());
+
+    // inside of a larger stream
+    let mut stream = data.to_vec();
+    stream.extend_from_slice(b"outer");
+    let mut parser = Parser::new(stream.as_slice()).verify_checksums(false);
+    parser.parse().unwrap();
+    assert_eq!(parser.bytes_consumed(), data.len());
+    assert_eq!(&stream[parser.bytes_consumed()..], b"outer");
+}
+
 #[test]
 fn parser_blob() {
     // copied from python
@@ -362,17 +2252,283 @@ fn parser_blob() {
 
     let expected = Item::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
 
-    let mut parser = Parser::new(Box::new(data.as_slice()));
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse().unwrap();
+
+    assert_eq!(item, Some(expected));
+}
+
+#[test]
+fn parser_blob_truncated_hash() {
+    let data = b"BSDF\x02\x02b\n\n\n\x00\xff";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    assert_eq!(parser.parse().map_err(Error::into_inner), Err(Error::Eof));
+}
+
+#[test]
+fn parser_blob_raw() {
+    // copied from python
+    let data = b"BSDF\x02\x02b\n\n\n\x00\xff\x7fc\xcbm\x06yr\xc3\xf3O\tK\xb7\xe7v\xa8\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00";
+
+    let expected = BlobMeta {
+        allocated_size: 10,
+        used_size: 10,
+        data_size: 10,
+        compression: consts::COMPRESSION_NOT_SET,
+        checksum_setting: consts::CHECKSUM_SET,
+        checksum: Some(b"\x7fc\xcbm\x06yr\xc3\xf3O\tK\xb7\xe7v\xa8".to_vec()),
+        alignment: 3,
+    };
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let (meta, blob) = parser.parse_blob_raw().unwrap();
+
+    assert_eq!(meta, expected);
+    assert_eq!(blob, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+}
+
+#[test]
+fn parser_blob_raw_not_a_blob() {
+    let data = b"BSDF\x02\x02h\x01\x00";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    assert_eq!(
+        parser.parse_blob_raw().map_err(Error::into_inner),
+        Err(Error::TypeMismatch {
+            expected: "blob",
+            found: "int16"
+        })
+    );
+}
+
+#[test]
+fn parser_blob_skip_verify() {
+    // blob fixture with the last hash byte changed
+    let data = b"BSDF\x02\x02b\n\n\n\x00\xff\x7fc\xcbm\x06yr\xc3\xf3O\tK\xb7\xe7v\x00\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00";
+
+    let expected = Item::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+
+    let mut parser = Parser::new(data.as_slice()).verify_checksums(false);
 
     let item = parser.parse().unwrap();
 
     assert_eq!(item, Some(expected));
 }
 
+#[cfg(not(feature = "md5"))]
+#[test]
+fn parser_blob_verify_md5_not_compiled() {
+    // copied from python
+    let data = b"BSDF\x02\x02b\n\n\n\x00\xff\x7fc\xcbm\x06yr\xc3\xf3O\tK\xb7\xe7v\xa8\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00";
+
+    let mut parser = Parser::new(data.as_slice()).verify_checksums(true);
+
+    assert_eq!(
+        parser.parse().map_err(Error::into_inner),
+        Err(Error::from(crate::InvalidExtension::Md5NotCompiled))
+    );
+}
+
+#[test]
+fn parser_blob_used_exceeds_allocated() {
+    // allocated 5, used 10, data 10
+    let data = b"BSDF\x02\x02b\x05\n\n\x00\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t";
+
+    let mut parser = Parser::new(data.as_slice());
+    assert_eq!(
+        parser.parse().map_err(Error::into_inner),
+        Err(Error::InvalidBlob)
+    );
+
+    let mut parser = Parser::new(data.as_slice());
+    assert_eq!(
+        parser.parse_borrowed().map_err(Error::into_inner),
+        Err(Error::InvalidBlob)
+    );
+}
+
+#[test]
+fn parser_depth_exceeded() {
+    let mut data = b"BSDF\x02\x02".to_vec();
+    data.extend(b"l\x01".repeat(10_000));
+    data.push(b'v');
+
+    let mut parser = Parser::new(data.as_slice());
+    assert_eq!(
+        parser.parse().map_err(Error::into_inner),
+        Err(Error::DepthExceeded)
+    );
+
+    let mut parser = Parser::new(data.as_slice());
+    assert_eq!(
+        parser.parse_borrowed().map_err(Error::into_inner),
+        Err(Error::DepthExceeded)
+    );
+
+    // the default depth has to fit on the stack of a test thread, even in debug builds
+    let mut data = b"BSDF\x02\x02".to_vec();
+    data.extend(b"l\x01".repeat(Limits::default().max_depth));
+    data.push(b'v');
+
+    let mut parser = Parser::new(data.as_slice());
+    assert!(parser.parse().is_ok());
+    let mut parser = Parser::new(data.as_slice());
+    assert!(parser.parse_borrowed().is_ok());
+}
+
+#[test]
+fn parser_depth_limit() {
+    let limits = Limits {
+        max_depth: 2,
+        ..Limits::default()
+    };
+
+    let data = b"BSDF\x02\x02l\x01m\x01\x01av";
+    let mut parser = Parser::with_limits(data.as_slice(), limits);
+    assert_eq!(
+        parser.parse(),
+        Ok(Some(Item::list([Item::map([(
+            String::from("a"),
+            Item::Void
+        )])])))
+    );
+
+    let data = b"BSDF\x02\x02l\x01m\x01\x01al\x00";
+    let mut parser = Parser::with_limits(data.as_slice(), limits);
+    assert_eq!(
+        parser.parse().map_err(Error::into_inner),
+        Err(Error::DepthExceeded)
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parser_nested_blob_spare() {
+    let item = Item::map([(
+        String::from("data"),
+        Item::CompressedBlob(Blob::new(vec![1; 10], Compression::None).with_spare(22)),
+    )]);
+
+    let mut data = Vec::new();
+    crate::Encoder::new(&mut data).write(&item).unwrap();
+    assert_eq!(data.len(), crate::encoded_size(&item));
+
+    let parsed = Parser::new(data.as_slice())
+        .keep_blob_compression(true)
+        .parse()
+        .unwrap()
+        .unwrap();
+    match parsed.get("data") {
+        Some(Item::CompressedBlob(blob)) => {
+            assert_eq!(blob.data, vec![1; 10]);
+            assert_eq!(blob.spare, 22);
+        }
+        other => panic!("expected a blob, got {:?}", other),
+    }
+
+    let mut written = Vec::new();
+    crate::Encoder::new(&mut written).write(&parsed).unwrap();
+    assert_eq!(written, data);
+}
+
+#[cfg(all(test, feature = "ordered-map"))]
+mod ordered_map_test {
+    use super::*;
+
+    #[test]
+    fn parses_map_in_stream_order() {
+        let data = b"BSDF\x02\x02m\x03\x01ch\x01\x00\x01ah\x02\x00\x01bh\x03\x00";
+
+        let item = Parser::new(data.as_slice()).parse().unwrap().unwrap();
+        let keys: Vec<&str> = item.as_map().unwrap().keys().map(String::as_str).collect();
+        assert_eq!(keys, ["c", "a", "b"]);
+
+        // the encoder writes the keys in the same order, so the document survives byte exact
+        assert_eq!(crate::to_vec(&item).unwrap(), data);
+
+        let mut parser = Parser::new(data.as_slice());
+        let Some(BorrowedItem::Map(map)) = parser.parse_borrowed().unwrap() else {
+            panic!("fixture is a map");
+        };
+        let keys: Vec<&str> = map.keys().map(|key| key.as_ref()).collect();
+        assert_eq!(keys, ["c", "a", "b"]);
+    }
+}
+
+#[cfg(all(test, feature = "md5"))]
+mod md5_test {
+    use super::*;
+
+    #[test]
+    fn parser_blob_verify() {
+        // blob fixture with the last hash byte changed
+        let data = b"BSDF\x02\x02b\n\n\n\x00\xff\x7fc\xcbm\x06yr\xc3\xf3O\tK\xb7\xe7v\x00\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00";
+
+        let mut parser = Parser::new(data.as_slice()).verify_checksums(true);
+
+        assert_eq!(
+            parser.parse().map_err(Error::into_inner),
+            Err(Error::InvalidBlobHash)
+        );
+    }
+
+    #[test]
+    fn parser_blob_verify_chunked() {
+        // large enough to be read and hashed in several chunks
+        let data: Vec<u8> = (0..3 * MAX_PREALLOCATE).map(|i| i as u8).collect();
+        let mut buffer = Vec::new();
+        crate::Encoder::new(&mut buffer)
+            .checksum(consts::CHECKSUM_SET)
+            .write(&Item::Blob(data.clone()))
+            .unwrap();
+
+        let mut parser = Parser::new(buffer.as_slice()).verify_checksums(true);
+        assert_eq!(parser.parse(), Ok(Some(Item::Blob(data.clone()))));
+
+        // a byte in the last chunk
+        let position = buffer.len() - 10;
+        buffer[position] ^= 1;
+        let mut parser = Parser::new(buffer.as_slice()).verify_checksums(true);
+        assert_eq!(
+            parser.parse().map_err(Error::into_inner),
+            Err(Error::InvalidBlobHash)
+        );
+
+        let mut scratch = Vec::new();
+        let mut parser = Parser::new(buffer.as_slice()).verify_checksums(true);
+        assert_eq!(
+            parser
+                .parse_with_scratch(&mut scratch)
+                .map_err(Error::into_inner),
+            Err(Error::InvalidBlobHash)
+        );
+    }
+}
+
 #[cfg(all(test, feature = "zlib"))]
 mod zlib_test {
     use super::*;
 
+    #[test]
+    fn parser_blob_raw_zlib() {
+        // copied from python
+        let data = b"BSDF\x02\x02b\xfd\x12\x00\x00\x00\x00\x00\x00\x00\xfd\x12\x00\x00\x00\x00\x00\x00\x00\xfd\n\x00\x00\x00\x00\x00\x00\x00\x01\xff\x01&\xdcT\xa3\xfcr\x7f\x1f\x14sM\xb66\x05i\x00x\xdacdbfaec\xe7\xe0d\x00\x00\x00\xdc\x00.";
+
+        let mut parser = Parser::new(data.as_slice());
+
+        let (meta, blob) = parser.parse_blob_raw().unwrap();
+
+        assert_eq!(meta.compression, consts::COMPRESSION_ZLIB);
+        assert_eq!(meta.used_size, 18);
+        assert_eq!(meta.data_size, 10);
+        assert_eq!(blob.len(), 18);
+    }
+
     #[test]
     fn parser_blob_zlib() {
         // copied from python
@@ -380,12 +2536,142 @@ mod zlib_test {
 
         let expected = Item::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
 
-        let mut parser = Parser::new(Box::new(data.as_slice()));
+        let mut parser = Parser::new(data.as_slice());
 
         let item = parser.parse().unwrap();
 
         assert_eq!(item, Some(expected));
     }
+
+    #[test]
+    fn parser_borrowed_blob_zlib() {
+        // copied from python
+        let data = b"BSDF\x02\x02b\xfd\x12\x00\x00\x00\x00\x00\x00\x00\xfd\x12\x00\x00\x00\x00\x00\x00\x00\xfd\n\x00\x00\x00\x00\x00\x00\x00\x01\xff\x01&\xdcT\xa3\xfcr\x7f\x1f\x14sM\xb66\x05i\x00x\xdacdbfaec\xe7\xe0d\x00\x00\x00\xdc\x00.";
+
+        let mut parser = Parser::new(data.as_slice());
+
+        let item = parser.parse_borrowed().unwrap();
+
+        // compressed data can't be borrowed
+        assert_eq!(
+            item,
+            Some(BorrowedItem::Blob(Cow::Owned(vec![
+                1, 2, 3, 4, 5, 6, 7, 8, 9, 0
+            ])))
+        );
+    }
+
+    #[test]
+    fn parser_blob_zlib_size_mismatch() {
+        let write = |data_size| blob::zlib_blob_document(&[0; 1 << 20], data_size, 0);
+
+        // a megabyte of zeros declared as ten bytes doesn't get past the declared size
+        let data = write(10);
+        assert!(data.len() < 2048);
+        assert_eq!(
+            Parser::new(data.as_slice())
+                .parse()
+                .map_err(Error::into_inner),
+            Err(Error::LimitExceeded)
+        );
+        assert_eq!(
+            Parser::from_bytes(&data)
+                .parse_borrowed()
+                .map_err(Error::into_inner),
+            Err(Error::LimitExceeded)
+        );
+
+        let data = write((1 << 20) + 1);
+        assert_eq!(
+            Parser::new(data.as_slice())
+                .parse()
+                .map_err(Error::into_inner),
+            Err(Error::InvalidBlob)
+        );
+
+        let data = write(1 << 20);
+        assert_eq!(
+            Parser::new(data.as_slice()).parse(),
+            Ok(Some(Item::Blob(vec![0; 1 << 20])))
+        );
+    }
+
+    #[test]
+    fn parser_lenient_corrupt_zlib() {
+        // the deflate stream is cut off, the document around it is complete
+        let blob = blob::zlib_blob_document(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 0], 10, 6);
+        let mut data = b"BSDF\x02\x02l\x02h\x01\x00".to_vec();
+        // without the header of the blob document
+        data.extend_from_slice(&blob[6..]);
+
+        assert_eq!(
+            Parser::new(data.as_slice())
+                .parse_lenient()
+                .map_err(Error::into_inner),
+            Err(Error::InvalidBlob)
+        );
+    }
+
+    #[test]
+    fn parser_keep_blob_compression() {
+        let item = Item::list([
+            Item::CompressedBlob(Blob::new(vec![1; 100], Compression::Zlib)),
+            Item::CompressedBlob(Blob::new(vec![2; 100], Compression::None)),
+        ]);
+
+        let mut data = Vec::new();
+        crate::Encoder::new(&mut data).write(&item).unwrap();
+
+        let parsed = Parser::new(data.as_slice())
+            .keep_blob_compression(true)
+            .parse()
+            .unwrap();
+        assert_eq!(parsed, Some(item));
+
+        let parsed = Parser::new(data.as_slice()).parse().unwrap();
+        assert_eq!(
+            parsed,
+            Some(Item::list([
+                Item::Blob(vec![1; 100]),
+                Item::Blob(vec![2; 100])
+            ]))
+        );
+    }
+}
+
+#[cfg(not(feature = "half"))]
+#[test]
+fn parser_half_not_compiled() {
+    let data = b"BSDF\x02\x02e\x00\x3e";
+
+    assert_eq!(
+        Parser::new(data.as_slice())
+            .parse()
+            .map_err(Error::into_inner),
+        Err(Error::from(crate::InvalidExtension::HalfNotCompiled))
+    );
+    // skipping does not need to decode it
+    assert_eq!(Parser::new(data.as_slice()).skip_item(), Ok(()));
+}
+
+#[cfg(all(test, feature = "half"))]
+mod half_test {
+    use super::*;
+
+    #[test]
+    fn parser_half() {
+        // 1.5 and -2.0 as half precision floats
+        let data = b"BSDF\x02\x02l\x02e\x00\x3ee\x00\xc0";
+
+        assert_eq!(
+            Parser::new(data.as_slice()).parse(),
+            Ok(Some(Item::List(vec![Item::F32(1.5), Item::F32(-2.0)])))
+        );
+        assert_eq!(
+            Parser::new(data.as_slice()).parse_f64_array(),
+            Ok(Some(vec![1.5, -2.0]))
+        );
+    }
 }
 
 #[cfg(all(test, feature = "bz2"))]
@@ -399,7 +2685,7 @@ mod bz2_test {
 
         let expected = Item::Blob(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
 
-        let mut parser = Parser::new(Box::new(data.as_slice()));
+        let mut parser = Parser::new(data.as_slice());
 
         let item = parser.parse().unwrap();
 
@@ -407,6 +2693,7 @@ mod bz2_test {
     }
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn parses_from_reader() {
     // copied from python
@@ -419,7 +2706,338 @@ fn parses_from_reader() {
         (String::from("test3"), Item::Int16(4)),
     ]));
 
-    let mut parser = Parser::new(Box::new(cursor));
+    let mut parser = Parser::new(cursor);
+
+    let item = parser.parse().unwrap();
+
+    assert_eq!(item, Some(expected));
+}
+
+#[test]
+fn parses_string_over_limit() {
+    let data = b"BSDF\x02\x02s\xfd\xff\xff\xff\xff\xff\xff\xff\xffabc";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    assert_eq!(
+        parser.parse().map_err(Error::into_inner),
+        Err(Error::LimitExceeded)
+    );
+}
+
+#[test]
+fn parses_with_limits() {
+    let data = b"BSDF\x02\x02l\x03vvv";
+    let limits = Limits {
+        max_length: 2,
+        ..Limits::default()
+    };
+
+    let mut parser = Parser::with_limits(data.as_slice(), limits);
+
+    assert_eq!(
+        parser.parse().map_err(Error::into_inner),
+        Err(Error::LimitExceeded)
+    );
+
+    let data = b"BSDF\x02\x02l\x02s\x03abcs\x03def";
+    let limits = Limits {
+        max_length: 2,
+        max_bytes: 2 * core::mem::size_of::<Item>() + 5,
+        ..Limits::default()
+    };
+
+    let mut parser = Parser::with_limits(data.as_slice(), limits);
+
+    assert_eq!(
+        parser.parse().map_err(Error::into_inner),
+        Err(Error::LimitExceeded)
+    );
+}
+
+#[test]
+fn parses_truncated_large_list() {
+    // declares a billion elements but has none
+    let data = b"BSDF\x02\x02l\xfd\x00\xca\x9a\x3b\x00\x00\x00\x00";
+    let limits = Limits {
+        max_length: usize::MAX,
+        max_bytes: usize::MAX,
+        ..Limits::default()
+    };
+
+    let mut parser = Parser::with_limits(data.as_slice(), limits);
+
+    // the data ends where the first element would start, not inside a value, so this is
+    // `MissingData` like any list that is short of elements. `Eof` is for a value that is cut
+    // off, see `parses_truncated_large_string`. both are incomplete input
+    let error = parser.parse().unwrap_err();
+    assert!(error.is_incomplete());
+    assert_eq!(error.into_inner(), Error::MissingData);
+}
+
+#[test]
+fn parses_truncated_large_string() {
+    let data = b"BSDF\x02\x02s\xfd\x00\xca\x9a\x3b\x00\x00\x00\x00abc";
+    let limits = Limits {
+        max_length: usize::MAX,
+        max_bytes: usize::MAX,
+        ..Limits::default()
+    };
+
+    let mut parser = Parser::with_limits(data.as_slice(), limits);
+
+    assert_eq!(parser.parse().map_err(Error::into_inner), Err(Error::Eof));
+}
+
+#[test]
+fn parses_list_iter() {
+    let data = b"BSDF\x02\x02l\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00";
+
+    let expected = Parser::new(data.as_slice()).parse().unwrap().unwrap();
+
+    let iter = Parser::new(data.as_slice()).into_list_iter().unwrap();
+    let items: Result<Vec<Item>, Error> = iter.collect();
+
+    assert_eq!(Item::List(items.unwrap()), expected);
+}
+
+#[test]
+fn parses_list_iter_limits() {
+    // ten 20 byte strings are more than the budget together, but not one by one
+    let mut data = b"BSDF\x02\x02l\x0a".to_vec();
+    for _ in 0..10 {
+        data.extend_from_slice(b"s\x14");
+        data.extend_from_slice(&[b'x'; 20]);
+    }
+    let limits = Limits {
+        max_bytes: 100,
+        ..Limits::default()
+    };
+
+    let items = Parser::with_limits(data.as_slice(), limits)
+        .into_list_iter()
+        .unwrap()
+        .collect::<Result<Vec<_>, Error>>()
+        .unwrap();
+    assert_eq!(items, vec![Item::from("x".repeat(20)); 10]);
+}
+
+#[test]
+fn parses_list_iter_truncated() {
+    let data = b"BSDF\x02\x02l\x03h\xff\xffn";
+
+    let mut iter = Parser::new(data.as_slice()).into_list_iter().unwrap();
+
+    assert_eq!(iter.next(), Some(Ok(Item::Int16(-1))));
+    assert_eq!(iter.next(), Some(Ok(Item::Bool(false))));
+    assert_eq!(
+        iter.next(),
+        Some(Err(Error::At {
+            offset: 12,
+            source: Box::new(Error::MissingData)
+        }))
+    );
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn parses_list_iter_not_a_list() {
+    let data = b"BSDF\x02\x02m\x00";
+
+    let result = Parser::new(data.as_slice())
+        .into_list_iter()
+        .map_err(Error::into_inner);
+
+    assert!(matches!(
+        result,
+        Err(Error::TypeMismatch {
+            expected: "list",
+            found: "map"
+        })
+    ));
+}
+
+#[test]
+fn parses_open_list_stream() {
+    // written like python's ListStream before it is closed
+    let data = b"BSDF\x02\x02l\xff\x00\x00\x00\x00\x00\x00\x00\x00h\x01\x00s\x04texty";
+
+    let expected = Item::List(vec![
+        Item::Int16(1),
+        Item::String(String::from("text")),
+        Item::Bool(true),
+    ]);
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse().unwrap();
+
+    assert_eq!(item, Some(expected));
+
+    let iter = Parser::new(data.as_slice()).into_list_iter().unwrap();
+    let items: Result<Vec<Item>, Error> = iter.collect();
+
+    assert_eq!(items.unwrap().len(), 3);
+}
+
+#[test]
+fn parses_closed_list_stream() {
+    // written like python's ListStream after it is closed, trailing data is not part of the list
+    let data = b"BSDF\x02\x02l\xfe\x02\x00\x00\x00\x00\x00\x00\x00h\x01\x00s\x04texty";
+
+    let expected = Item::List(vec![Item::Int16(1), Item::String(String::from("text"))]);
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse().unwrap();
+
+    assert_eq!(item, Some(expected));
+}
+
+#[test]
+fn parses_nested_open_list_stream() {
+    let data = b"BSDF\x02\x02m\x01\x04listl\xff\x00\x00\x00\x00\x00\x00\x00\x00vv";
+
+    let expected = Item::Map(Map::from_iter([(
+        String::from("list"),
+        Item::List(vec![Item::Void, Item::Void]),
+    )]));
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse().unwrap();
+
+    assert_eq!(item, Some(expected));
+}
+
+#[test]
+fn parses_open_map_stream() {
+    let data = b"BSDF\x02\x02m\xff\x00\x00\x00\x00\x00\x00\x00\x00\x01ay\x01bn";
+
+    let expected = Item::Map(Map::from_iter([
+        (String::from("a"), Item::Bool(true)),
+        (String::from("b"), Item::Bool(false)),
+    ]));
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse().unwrap();
+
+    assert_eq!(item, Some(expected));
+}
+
+#[test]
+fn parses_borrowed_string() {
+    let text = "x".repeat(10_000);
+    let mut data = b"BSDF\x02\x02s\xfd".to_vec();
+    data.extend_from_slice(&(text.len() as u64).to_le_bytes());
+    data.extend_from_slice(text.as_bytes());
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse_borrowed().unwrap();
+
+    match item {
+        Some(BorrowedItem::String(Cow::Borrowed(parsed))) => {
+            assert_eq!(parsed, text);
+            // the payload was not copied
+            assert!(data.as_ptr_range().contains(&parsed.as_ptr()));
+        }
+        other => panic!("expected a borrowed string, got {:?}", other),
+    }
+}
+
+#[test]
+fn parses_borrowed_nested_map() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+
+    let expected = Parser::new(data.as_slice()).parse().unwrap();
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse_borrowed().unwrap();
+
+    assert_eq!(item.map(BorrowedItem::into_owned), expected);
+}
+
+#[test]
+fn parses_borrowed_blob() {
+    // copied from python
+    let data = b"BSDF\x02\x02b\n\n\n\x00\xff\x7fc\xcbm\x06yr\xc3\xf3O\tK\xb7\xe7v\xa8\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse_borrowed().unwrap();
+
+    match item {
+        Some(BorrowedItem::Blob(Cow::Borrowed(parsed))) => {
+            assert_eq!(parsed, [1, 2, 3, 4, 5, 6, 7, 8, 9, 0]);
+            assert!(data.as_ptr_range().contains(&parsed.as_ptr()));
+        }
+        other => panic!("expected a borrowed blob, got {:?}", other),
+    }
+}
+
+#[test]
+fn parses_borrowed_list_stream() {
+    let data = b"BSDF\x02\x02l\xff\x00\x00\x00\x00\x00\x00\x00\x00m\xff\x00\x00\x00\x00\x00\x00\x00\x00\x01ay";
+
+    let expected = Parser::new(data.as_slice()).parse().unwrap();
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let item = parser.parse_borrowed().unwrap();
+
+    assert_eq!(item.map(BorrowedItem::into_owned), expected);
+}
+
+#[test]
+fn parses_error_offset() {
+    // nested map fixture, cut off in the middle of the int64 inside the list
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let error = parser.parse().unwrap_err();
+
+    assert_eq!(error.offset(), Some(data.len()));
+    assert_eq!(
+        error,
+        Error::At {
+            offset: data.len(),
+            source: Box::new(Error::Eof)
+        }
+    );
+    assert_eq!(error.to_string(), "sudden missing data at byte 47");
+}
+
+#[test]
+fn parses_error_offset_invalid_size() {
+    let data = b"BSDF\x02\x02l\x02vs\xfch";
+
+    let mut parser = Parser::new(data.as_slice());
+
+    let error = parser.parse().unwrap_err();
+
+    // right after the invalid size byte
+    assert_eq!(error.offset(), Some(11));
+    assert_eq!(error.into_inner(), Error::InvalidSize);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn parses_from_boxed_reader() {
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+    let reader: Box<dyn Read> = Box::new(data.as_slice());
+
+    let expected = Item::Map(Map::from_iter([
+        (String::from("test"), Item::Int16(1)),
+        (String::from("test1"), Item::Int16(2)),
+        (String::from("test3"), Item::Int16(4)),
+    ]));
+
+    let mut parser: Parser<Box<dyn Read>> = Parser::new(reader);
 
     let item = parser.parse().unwrap();
 
@@ -0,0 +1,373 @@
+// the same parsing as `Parser::parse`, but awaiting every read. blobs are read completely before
+// they are verified and decompressed, that part is not async
+
+use super::{
+    check_version, extension_type, f16_to_f32, header_version, is_extension, Limits,
+    MAX_PREALLOCATE,
+};
+use crate::{blob, consts, item, BlobMeta, Error, Item};
+use alloc::borrow::Cow;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// parser for `tokio::io::AsyncRead`, reads are done a byte at a time like `Parser` so wrap
+/// sockets and files in a `tokio::io::BufReader`
+pub struct AsyncParser<R> {
+    reader: R,
+    peeked: Option<u8>,
+    // bytes consumed so far, for the offset in errors
+    position: usize,
+    version: Option<u16>,
+    limits: Limits,
+    allocated: usize,
+    depth: usize,
+    verify_checksums: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncParser<R> {
+    pub fn new(reader: R) -> AsyncParser<R> {
+        Self::with_limits(reader, Limits::default())
+    }
+
+    pub fn with_limits(reader: R, limits: Limits) -> AsyncParser<R> {
+        AsyncParser {
+            reader,
+            peeked: None,
+            position: 0,
+            version: None,
+            limits,
+            allocated: 0,
+            depth: 0,
+            verify_checksums: cfg!(feature = "md5"),
+        }
+    }
+
+    /// see `Parser::verify_checksums`
+    pub fn verify_checksums(mut self, verify: bool) -> Self {
+        self.verify_checksums = verify;
+        self
+    }
+
+    /// the (major, minor) version from the header, available once the header is parsed
+    pub fn version(&self) -> Option<(u8, u8)> {
+        self.version.map(|version| {
+            let [major, minor] = version.to_le_bytes();
+            (major, minor)
+        })
+    }
+
+    pub async fn parse(&mut self) -> Result<Option<Item>, Error> {
+        let result = match self.parse_header().await {
+            Ok(()) => self.parse_item().await,
+            Err(e) => Err(e),
+        };
+        result.map_err(|source| Error::At {
+            offset: self.position,
+            source: Box::new(source),
+        })
+    }
+
+    async fn parse_header(&mut self) -> Result<(), Error> {
+        self.allocated = 0;
+
+        let mut buffer = [0; 6];
+        for byte in &mut buffer {
+            *byte = self.read_byte().await?.ok_or(Error::MissingData)?;
+        }
+
+        let version = header_version(&buffer)?;
+        self.version = Some(version);
+        check_version(version)
+    }
+
+    async fn read_byte(&mut self) -> Result<Option<u8>, Error> {
+        let byte = match self.peeked.take() {
+            Some(byte) => Some(byte),
+            None => {
+                let mut byte = 0;
+                match self.reader.read(core::slice::from_mut(&mut byte)).await? {
+                    0 => None,
+                    _ => Some(byte),
+                }
+            }
+        };
+        if byte.is_some() {
+            self.position += 1;
+        }
+        Ok(byte)
+    }
+
+    async fn peek(&mut self) -> Result<Option<u8>, Error> {
+        if self.peeked.is_none() {
+            let byte = self.read_byte().await?;
+            // peeked bytes only count once they are consumed
+            self.position -= usize::from(byte.is_some());
+            self.peeked = byte;
+        }
+        Ok(self.peeked)
+    }
+
+    async fn next(&mut self) -> Result<u8, Error> {
+        self.read_byte().await?.ok_or(Error::Eof)
+    }
+
+    async fn read_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut buffer = [0; N];
+        for byte in &mut buffer {
+            *byte = self.next().await?;
+        }
+        Ok(buffer)
+    }
+
+    async fn read_bytes(&mut self, n: usize) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::with_capacity(n.min(MAX_PREALLOCATE));
+        if n == 0 {
+            return Ok(data);
+        }
+        if let Some(byte) = self.peeked.take() {
+            data.push(byte);
+        }
+        let rest = (n - data.len()) as u64;
+        (&mut self.reader).take(rest).read_to_end(&mut data).await?;
+        self.position += data.len();
+        if data.len() != n {
+            return Err(Error::Eof);
+        }
+        Ok(data)
+    }
+
+    fn allocate(&mut self, bytes: usize) -> Result<(), Error> {
+        self.allocated = self
+            .allocated
+            .checked_add(bytes)
+            .filter(|allocated| *allocated <= self.limits.max_bytes)
+            .ok_or(Error::LimitExceeded)?;
+        Ok(())
+    }
+
+    fn allocate_collection(&mut self, length: usize) -> Result<(), Error> {
+        if length > self.limits.max_length {
+            return Err(Error::LimitExceeded);
+        }
+        self.allocate(length.saturating_mul(core::mem::size_of::<Item>()))
+    }
+
+    async fn parse_item(&mut self) -> Result<Option<Item>, Error> {
+        let next_byte = match self.read_byte().await? {
+            None => return Ok(None),
+            Some(byte) => byte,
+        };
+        let next_byte = match is_extension(next_byte) {
+            true => {
+                let name = self.parse_string().await?;
+                extension_type(next_byte, name)?
+            }
+            false => next_byte,
+        };
+
+        let item = match next_byte {
+            b'v' => Item::Void,
+            b'n' => Item::Bool(false),
+            b'y' => Item::Bool(true),
+            b'h' => Item::Int16(i16::from_le_bytes(self.read_array().await?)),
+            b'i' => Item::Int64(i64::from_le_bytes(self.read_array().await?)),
+            b'f' => Item::F32(f32::from_le_bytes(self.read_array().await?)),
+            consts::TYPE_F16 => Item::F32(f16_to_f32(self.read_array().await?)?),
+            b'd' => Item::F64(f64::from_le_bytes(self.read_array().await?)),
+            b's' => Item::String(self.parse_string().await?),
+            b'l' | b'm' => {
+                if self.depth == self.limits.max_depth {
+                    return Err(Error::DepthExceeded);
+                }
+                self.depth += 1;
+                // the future of a recursive async fn has to be boxed
+                let item = match next_byte {
+                    b'l' => Box::pin(self.parse_list()).await,
+                    _ => Box::pin(self.parse_map()).await,
+                };
+                self.depth -= 1;
+                item?
+            }
+            b'b' => Item::Blob(self.parse_blob().await?),
+            byte => return Err(Error::InvalidType(byte)),
+        };
+
+        Ok(Some(item))
+    }
+
+    async fn parse_string(&mut self) -> Result<String, Error> {
+        let length = self.parse_size().await?;
+        self.allocate(length)?;
+
+        let text_data = self.read_bytes(length).await?;
+        String::from_utf8(text_data).map_err(|e| Error::InvalidUtf8(e.into_bytes()))
+    }
+
+    async fn parse_size(&mut self) -> Result<usize, Error> {
+        let first_byte = self.next().await?;
+        self.parse_size_from(first_byte).await
+    }
+
+    async fn parse_size_from(&mut self, first_byte: u8) -> Result<usize, Error> {
+        match consts::decode_small_size(first_byte)? {
+            Some(size) => Ok(size),
+            None => self.parse_usize().await,
+        }
+    }
+
+    /// see `Parser::parse_usize`
+    async fn parse_usize(&mut self) -> Result<usize, Error> {
+        usize::try_from(u64::from_le_bytes(self.read_array().await?))
+            .map_err(|_| Error::LimitExceeded)
+    }
+
+    /// see `Parser::parse_collection_size`
+    async fn parse_collection_size(&mut self) -> Result<Option<usize>, Error> {
+        match self.next().await? {
+            consts::STREAM_CLOSED => Ok(Some(self.parse_usize().await?)),
+            consts::STREAM_OPEN => {
+                self.read_array::<8>().await?;
+                Ok(None)
+            }
+            first_byte => Ok(Some(self.parse_size_from(first_byte).await?)),
+        }
+    }
+
+    async fn parse_list(&mut self) -> Result<Item, Error> {
+        let length = self.parse_collection_size().await?;
+        let mut list = Vec::with_capacity(length.unwrap_or(0).min(MAX_PREALLOCATE));
+
+        match length {
+            Some(length) => {
+                self.allocate_collection(length)?;
+
+                for _ in 0..length {
+                    list.push(self.parse_item().await?.ok_or(Error::MissingData)?);
+                }
+            }
+            None => {
+                while let Some(item) = self.parse_item().await? {
+                    if list.len() == self.limits.max_length {
+                        return Err(Error::LimitExceeded);
+                    }
+                    self.allocate(core::mem::size_of::<Item>())?;
+                    list.push(item);
+                }
+            }
+        }
+
+        Ok(Item::List(list))
+    }
+
+    async fn parse_map(&mut self) -> Result<Item, Error> {
+        let length = self.parse_collection_size().await?;
+        let mut map = item::map_with_capacity(length.unwrap_or(0).min(MAX_PREALLOCATE));
+
+        match length {
+            Some(length) => {
+                self.allocate_collection(length)?;
+
+                for _ in 0..length {
+                    let key = self.parse_string().await?;
+                    let value = self.parse_item().await?.ok_or(Error::MissingData)?;
+                    map.insert(key, value);
+                }
+            }
+            None => {
+                let mut length = 0;
+                while self.peek().await?.is_some() {
+                    if length == self.limits.max_length {
+                        return Err(Error::LimitExceeded);
+                    }
+                    length += 1;
+                    self.allocate(core::mem::size_of::<Item>())?;
+
+                    let key = self.parse_string().await?;
+                    let value = self.parse_item().await?.ok_or(Error::MissingData)?;
+                    map.insert(key, value);
+                }
+            }
+        }
+
+        Ok(Item::Map(map))
+    }
+
+    async fn parse_blob(&mut self) -> Result<Vec<u8>, Error> {
+        let allocated_size = self.parse_size().await?;
+        let used_size = self.parse_size().await?;
+        let data_size = self.parse_size().await?;
+        if used_size > allocated_size {
+            return Err(Error::InvalidBlob);
+        }
+        let compression = self.next().await?;
+        let checksum_setting = self.next().await?;
+        let checksum = match blob::checksum_len(checksum_setting) {
+            Some(length) => Some(self.read_bytes(length).await?),
+            None => None,
+        };
+        let alignment = self.next().await?;
+
+        self.allocate(used_size)?;
+        if compression != consts::COMPRESSION_NOT_SET {
+            self.allocate(data_size)?;
+        }
+
+        self.read_bytes(alignment as usize).await?;
+        let data = self.read_bytes(used_size).await?;
+        self.read_bytes(allocated_size - used_size).await?;
+
+        let meta = BlobMeta {
+            allocated_size,
+            used_size,
+            data_size,
+            compression,
+            checksum_setting,
+            checksum,
+            alignment,
+        };
+        blob::unpack(&meta, Cow::Owned(data), self.verify_checksums).map(Cow::into_owned)
+    }
+}
+
+#[tokio::test]
+async fn async_parses_normal_map() {
+    // copied from python
+    let data = b"BSDF\x02\x02m\x03\x04testh\x01\x00\x05test1h\x02\x00\x05test3h\x04\x00";
+
+    let expected = Item::map([
+        (String::from("test"), Item::Int16(1)),
+        (String::from("test1"), Item::Int16(2)),
+        (String::from("test3"), Item::Int16(4)),
+    ]);
+
+    let mut parser = AsyncParser::new(std::io::Cursor::new(data));
+
+    assert_eq!(parser.parse().await.unwrap(), Some(expected));
+    assert_eq!(parser.version(), Some((2, 2)));
+}
+
+#[tokio::test]
+async fn async_matches_parser() {
+    use crate::Parser;
+
+    // copied from python
+    let nested = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text".as_slice();
+    let stream = b"BSDF\x02\x02l\xff\x00\x00\x00\x00\x00\x00\x00\x00h\x01\x00s\x04textm\xff\x00\x00\x00\x00\x00\x00\x00\x00\x01ay".as_slice();
+    let blob = b"BSDF\x02\x02b\n\n\n\x00\x00\x03\x00\x00\x00\x01\x02\x03\x04\x05\x06\x07\x08\t\x00"
+        .as_slice();
+
+    for data in [nested, stream, blob] {
+        let expected = Parser::new(data).parse().unwrap();
+        let item = AsyncParser::new(data).parse().await.unwrap();
+        assert_eq!(item, expected);
+    }
+}
+
+#[tokio::test]
+async fn async_truncated() {
+    let data = b"BSDF\x02\x02s\x04te";
+
+    let error = AsyncParser::new(data.as_slice()).parse().await.unwrap_err();
+
+    assert_eq!(error.offset(), Some(10));
+    assert_eq!(error.into_inner(), Error::Eof);
+}
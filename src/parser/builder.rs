@@ -0,0 +1,118 @@
+// the settings of a `Parser` without a reader, so the same configuration can be used for many
+// readers. `build` hands them to the setters of `Parser`
+
+use super::{Endian, Limits, Parser};
+use crate::io::Read;
+#[cfg(test)]
+use alloc::vec;
+
+/// configuration for `Parser`, every setter does the same as the `Parser` setter of the same name
+#[derive(Debug, Clone)]
+pub struct ParserBuilder {
+    limits: Limits,
+    verify_checksums: bool,
+    invalid_utf8_as_blob: bool,
+    utf8_lossy: bool,
+    reject_duplicate_keys: bool,
+    keep_blob_compression: bool,
+    widen_integers: bool,
+    endian: Endian,
+}
+
+impl Default for ParserBuilder {
+    /// the settings of `Parser::new`
+    fn default() -> ParserBuilder {
+        ParserBuilder {
+            limits: Limits::default(),
+            verify_checksums: cfg!(feature = "md5"),
+            invalid_utf8_as_blob: false,
+            utf8_lossy: false,
+            reject_duplicate_keys: false,
+            keep_blob_compression: false,
+            widen_integers: false,
+            endian: Endian::Little,
+        }
+    }
+}
+
+impl ParserBuilder {
+    pub fn new() -> ParserBuilder {
+        ParserBuilder::default()
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn verify_checksums(mut self, verify: bool) -> Self {
+        self.verify_checksums = verify;
+        self
+    }
+
+    pub fn invalid_utf8_as_blob(mut self, enabled: bool) -> Self {
+        self.invalid_utf8_as_blob = enabled;
+        self
+    }
+
+    pub fn utf8_lossy(mut self, enabled: bool) -> Self {
+        self.utf8_lossy = enabled;
+        self
+    }
+
+    pub fn reject_duplicate_keys(mut self, reject: bool) -> Self {
+        self.reject_duplicate_keys = reject;
+        self
+    }
+
+    pub fn keep_blob_compression(mut self, keep: bool) -> Self {
+        self.keep_blob_compression = keep;
+        self
+    }
+
+    pub fn widen_integers(mut self, widen: bool) -> Self {
+        self.widen_integers = widen;
+        self
+    }
+
+    pub fn endianness(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    /// a parser for `reader` with these settings, the builder can be used again
+    pub fn build<R: Read>(&self, reader: R) -> Parser<R> {
+        Parser::with_limits(reader, self.limits)
+            .verify_checksums(self.verify_checksums)
+            .invalid_utf8_as_blob(self.invalid_utf8_as_blob)
+            .utf8_lossy(self.utf8_lossy)
+            .reject_duplicate_keys(self.reject_duplicate_keys)
+            .keep_blob_compression(self.keep_blob_compression)
+            .widen_integers(self.widen_integers)
+            .endianness(self.endian)
+    }
+}
+
+#[test]
+fn builder_test() {
+    use crate::{Error, Item};
+
+    let builder = ParserBuilder::new()
+        .widen_integers(true)
+        .reject_duplicate_keys(true);
+
+    let data = b"BSDF\x02\x02l\x01h\x05\x00";
+    assert_eq!(
+        builder.build(data.as_slice()).parse(),
+        Ok(Some(Item::List(vec![Item::Int64(5)])))
+    );
+
+    let data = b"BSDF\x02\x02m\x02\x01ah\x01\x00\x01ah\x02\x00";
+    assert_eq!(
+        builder
+            .build(data.as_slice())
+            .parse()
+            .map_err(Error::into_inner),
+        Err(Error::DuplicateKey("a".into()))
+    );
+}
@@ -0,0 +1,250 @@
+// deserializes straight from the stream without building an `Item` first, the values are handed
+// to serde the same way the `Deserializer` of `Item` does
+
+use super::Parser;
+use crate::io::Read;
+use crate::{consts, Error, Item};
+use serde::de::{self, Deserialize, DeserializeSeed, Visitor};
+use serde::forward_to_deserialize_any;
+
+impl<R: Read> Parser<R> {
+    /// the next type byte, a missing value is `Error::MissingData` like in `parse`
+    fn next_type(&mut self) -> Result<u8, Error> {
+        match self.reader.next() {
+            None => Err(Error::MissingData),
+            Some(Err(e)) => Err(Error::from(e)),
+            Some(Ok(byte)) => Ok(byte),
+        }
+    }
+
+    /// deserializes a `T` from the document, the header is parsed first
+    pub(crate) fn deserialize<T: de::DeserializeOwned>(&mut self) -> Result<T, Error> {
+        self.at_offset(|parser| {
+            parser.parse_header()?;
+            T::deserialize(parser)
+        })
+    }
+}
+
+impl<'de, R: Read> de::Deserializer<'de> for &mut Parser<R> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let byte = self.next_type()?;
+        match self.resolve_extension(byte)? {
+            b'v' => visitor.visit_unit(),
+            b'n' => visitor.visit_bool(false),
+            b'y' => visitor.visit_bool(true),
+            b'h' => visitor.visit_i16(self.parse_int16()?),
+            b'i' => visitor.visit_i64(self.parse_int64()?),
+            b'f' => visitor.visit_f32(self.parse_f32()?),
+            consts::TYPE_F16 => visitor.visit_f32(self.parse_f16()?),
+            b'd' => visitor.visit_f64(self.parse_f64()?),
+            b's' => match self.parse_string() {
+                Err(Error::InvalidUtf8(data)) if self.invalid_utf8_as_blob => {
+                    visitor.visit_byte_buf(data)
+                }
+                text => visitor.visit_string(text?),
+            },
+            b'b' => visitor.visit_byte_buf(self.parse_blob()?),
+            b'l' => self.nested(|parser| {
+                let mut access = Access::new(parser)?;
+                let value = visitor.visit_seq(&mut access)?;
+                access.skip_rest(false)?;
+                Ok(value)
+            }),
+            b'm' => self.nested(|parser| {
+                let mut access = Access::new(parser)?;
+                let value = visitor.visit_map(&mut access)?;
+                access.skip_rest(true)?;
+                Ok(value)
+            }),
+            byte => Err(Error::InvalidType(byte)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.peek_byte()? == Some(b'v') {
+            self.next_type()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.next_type()? {
+            b's' => {
+                let variant = self.parse_string()?;
+                visitor.visit_enum(de::value::StringDeserializer::<Error>::new(variant))
+            }
+            b'm' => self.nested(|parser| match parser.parse_collection_size()? {
+                Some(1) => visitor.visit_enum(Enum { parser }),
+                _ => Err(Error::Serde(String::from(
+                    "enum variant with data must be a map with one entry",
+                ))),
+            }),
+            byte => Err(Error::TypeMismatch {
+                expected: "enum",
+                found: super::type_name(byte),
+            }),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// elements of a list or entries of a map, `remaining` is `None` for an open stream
+struct Access<'a, R: Read> {
+    parser: &'a mut Parser<R>,
+    remaining: Option<usize>,
+    read: usize,
+}
+
+impl<'a, R: Read> Access<'a, R> {
+    fn new(parser: &'a mut Parser<R>) -> Result<Access<'a, R>, Error> {
+        let remaining = parser.parse_collection_size()?;
+        if let Some(length) = remaining {
+            parser.allocate_collection(length)?;
+        }
+        Ok(Access {
+            parser,
+            remaining,
+            read: 0,
+        })
+    }
+
+    /// skips what the visitor didn't consume, so the stream stays in sync
+    fn skip_rest(&mut self, keys: bool) -> Result<(), Error> {
+        while self.has_next()? {
+            if keys {
+                self.parser.parse_string()?;
+            }
+            de::IgnoredAny::deserialize(&mut *self.parser)?;
+        }
+        Ok(())
+    }
+
+    fn has_next(&mut self) -> Result<bool, Error> {
+        match self.remaining {
+            Some(0) => Ok(false),
+            Some(remaining) => {
+                self.remaining = Some(remaining - 1);
+                Ok(true)
+            }
+            None => {
+                if self.parser.peek_byte()?.is_none() {
+                    return Ok(false);
+                }
+                if self.read == self.parser.limits.max_length {
+                    return Err(Error::LimitExceeded);
+                }
+                self.read += 1;
+                self.parser.allocate(core::mem::size_of::<Item>())?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+impl<'de, R: Read> de::SeqAccess<'de> for Access<'_, R> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if !self.has_next()? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.parser).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.remaining
+    }
+}
+
+impl<'de, R: Read> de::MapAccess<'de> for Access<'_, R> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if !self.has_next()? {
+            return Ok(None);
+        }
+        let key = self.parser.parse_string()?;
+        seed.deserialize(de::value::StringDeserializer::<Error>::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(&mut *self.parser)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.remaining
+    }
+}
+
+/// a variant with data, stored as a map with the variant name as the only key
+struct Enum<'a, R: Read> {
+    parser: &'a mut Parser<R>,
+}
+
+impl<'de, 'a, R: Read> de::EnumAccess<'de> for Enum<'a, R> {
+    type Error = Error;
+    type Variant = &'a mut Parser<R>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, &'a mut Parser<R>), Error> {
+        let variant = self.parser.parse_string()?;
+        let variant = seed.deserialize(de::value::StringDeserializer::<Error>::new(variant))?;
+        Ok((variant, self.parser))
+    }
+}
+
+impl<'de, R: Read> de::VariantAccess<'de> for &mut Parser<R> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
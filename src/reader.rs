@@ -0,0 +1,118 @@
+use crate::io::{self, Read};
+
+/// byte at a time reader with one byte of lookahead, like `Peekable<Bytes<R>>` but it still gives
+/// access to the inner reader. fixed size fields and data are read in bulk with `read_into`
+pub(crate) struct ByteReader<R> {
+    inner: R,
+    peeked: Option<Option<io::Result<u8>>>,
+    // bytes handed out so far, peeked bytes only count once they are consumed
+    position: usize,
+}
+
+impl<R: Read> ByteReader<R> {
+    pub fn new(inner: R) -> ByteReader<R> {
+        ByteReader {
+            inner,
+            peeked: None,
+            position: 0,
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn peek(&mut self) -> Option<&io::Result<u8>> {
+        if self.peeked.is_none() {
+            let byte = self.read_byte();
+            self.peeked = Some(byte);
+        }
+        self.peeked.as_ref().and_then(Option::as_ref)
+    }
+
+    /// fills `buf` with as many bytes as are left, returns how many were read. this is one `read`
+    /// call on the inner reader for the whole buffer instead of one per byte
+    pub fn read_into(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut filled = 0;
+        match self.peeked.take() {
+            None => {}
+            Some(None) => return Ok(0),
+            Some(Some(Err(e))) => return Err(e),
+            Some(Some(Ok(byte))) => {
+                buf[0] = byte;
+                filled = 1;
+            }
+        }
+
+        let result = loop {
+            if filled == buf.len() {
+                break Ok(filled);
+            }
+            match self.inner.read(&mut buf[filled..]) {
+                Ok(0) => break Ok(filled),
+                Ok(n) => filled += n,
+                Err(e) if is_interrupted(&e) => continue,
+                Err(e) => break Err(e),
+            }
+        };
+        self.position += filled;
+        result
+    }
+
+    fn read_byte(&mut self) -> Option<io::Result<u8>> {
+        let mut byte = 0;
+        loop {
+            return match self.inner.read(core::slice::from_mut(&mut byte)) {
+                Ok(0) => None,
+                Ok(_) => Some(Ok(byte)),
+                Err(e) if is_interrupted(&e) => continue,
+                Err(e) => Some(Err(e)),
+            };
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn is_interrupted(err: &io::Error) -> bool {
+    err.kind() == std::io::ErrorKind::Interrupted
+}
+
+#[cfg(not(feature = "std"))]
+fn is_interrupted(err: &io::Error) -> bool {
+    match *err {}
+}
+
+impl<'a> ByteReader<&'a [u8]> {
+    /// takes the next `n` bytes straight out of the underlying slice. a peeked byte is already
+    /// taken out of the slice and can't be part of the data, so this panics when there is one
+    pub fn take_slice(&mut self, n: usize) -> Option<&'a [u8]> {
+        // sizes are always read byte by byte right before the data, so nothing is peeked here
+        assert!(self.peeked.is_none(), "take_slice after peek");
+
+        if n > self.inner.len() {
+            return None;
+        }
+        let (data, rest) = self.inner.split_at(n);
+        self.inner = rest;
+        self.position += n;
+        Some(data)
+    }
+}
+
+impl<R: Read> Iterator for ByteReader<R> {
+    type Item = io::Result<u8>;
+
+    fn next(&mut self) -> Option<io::Result<u8>> {
+        let byte = match self.peeked.take() {
+            Some(byte) => byte,
+            None => self.read_byte(),
+        };
+        if let Some(Ok(_)) = byte {
+            self.position += 1;
+        }
+        byte
+    }
+}
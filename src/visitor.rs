@@ -0,0 +1,276 @@
+use crate::parser::MAX_PREALLOCATE;
+use crate::{item, Blob, Error, Item, Map};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// callbacks for `Parser::parse_with`, called in stream order while the parser walks the document.
+///
+/// every method does nothing by default, returning an error aborts the parse with that error
+pub trait Visitor {
+    fn on_void(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_bool(&mut self, _value: bool) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_int16(&mut self, _value: i16) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_int64(&mut self, _value: i64) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_f32(&mut self, _value: f32) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_f64(&mut self, _value: f64) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_string(&mut self, _value: String) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// the data is already decompressed and verified
+    fn on_blob(&mut self, _data: Vec<u8>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// instead of `on_blob` with `Parser::keep_blob_compression`, by default it forwards the data
+    /// to `on_blob`
+    fn on_compressed_blob(&mut self, blob: Blob) -> Result<(), Error> {
+        self.on_blob(blob.data)
+    }
+
+    /// `len` is `None` for an open stream
+    fn begin_list(&mut self, _len: Option<usize>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn end_list(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// `len` is `None` for an open stream
+    fn begin_map(&mut self, _len: Option<usize>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// called before the value of every entry
+    fn map_key(&mut self, _key: String) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn end_map(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+enum Frame {
+    List(Vec<Item>),
+    Map(Map, Option<String>),
+}
+
+/// the visitor behind `Parser::parse`, it builds the `Item` tree
+#[derive(Default)]
+pub(crate) struct TreeBuilder {
+    stack: Vec<Frame>,
+    root: Option<Item>,
+    reject_duplicate_keys: bool,
+    reject_open_streams: bool,
+}
+
+impl TreeBuilder {
+    pub fn new(reject_duplicate_keys: bool) -> TreeBuilder {
+        TreeBuilder {
+            reject_duplicate_keys,
+            ..TreeBuilder::default()
+        }
+    }
+
+    /// fail with `Error::OpenStream` on a list or map that runs until the end of the data
+    pub fn reject_open_streams(mut self) -> TreeBuilder {
+        self.reject_open_streams = true;
+        self
+    }
+
+    pub fn finish(self) -> Option<Item> {
+        self.root
+    }
+
+    /// the tree up to where the parse stopped, the lists and maps it was inside of are closed
+    /// with what they have. a map key without its value is dropped
+    pub fn finish_partial(mut self) -> Option<Item> {
+        while let Some(frame) = self.stack.pop() {
+            let item = match frame {
+                Frame::List(list) => Item::List(list),
+                Frame::Map(map, _) => Item::Map(map),
+            };
+            // only a duplicate key fails here, the map keeps the value it already had
+            let _ = self.value(item);
+        }
+        self.root
+    }
+
+    fn value(&mut self, item: Item) -> Result<(), Error> {
+        match self.stack.last_mut() {
+            None => self.root = Some(item),
+            Some(Frame::List(list)) => list.push(item),
+            Some(Frame::Map(map, key)) => {
+                // the parser always reports the key before the value
+                let key = key.take().ok_or(Error::MissingData)?;
+                if self.reject_duplicate_keys && map.contains_key(&key) {
+                    return Err(Error::DuplicateKey(key));
+                }
+                map.insert(key, item);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Visitor for TreeBuilder {
+    fn on_void(&mut self) -> Result<(), Error> {
+        self.value(Item::Void)
+    }
+
+    fn on_bool(&mut self, value: bool) -> Result<(), Error> {
+        self.value(Item::Bool(value))
+    }
+
+    fn on_int16(&mut self, value: i16) -> Result<(), Error> {
+        self.value(Item::Int16(value))
+    }
+
+    fn on_int64(&mut self, value: i64) -> Result<(), Error> {
+        self.value(Item::Int64(value))
+    }
+
+    fn on_f32(&mut self, value: f32) -> Result<(), Error> {
+        self.value(Item::F32(value))
+    }
+
+    fn on_f64(&mut self, value: f64) -> Result<(), Error> {
+        self.value(Item::F64(value))
+    }
+
+    fn on_string(&mut self, value: String) -> Result<(), Error> {
+        self.value(Item::String(value))
+    }
+
+    fn on_blob(&mut self, data: Vec<u8>) -> Result<(), Error> {
+        self.value(Item::Blob(data))
+    }
+
+    fn on_compressed_blob(&mut self, blob: Blob) -> Result<(), Error> {
+        self.value(Item::CompressedBlob(blob))
+    }
+
+    fn begin_list(&mut self, len: Option<usize>) -> Result<(), Error> {
+        if len.is_none() && self.reject_open_streams {
+            return Err(Error::OpenStream);
+        }
+        let capacity = len.unwrap_or(0).min(MAX_PREALLOCATE);
+        self.stack.push(Frame::List(Vec::with_capacity(capacity)));
+        Ok(())
+    }
+
+    fn end_list(&mut self) -> Result<(), Error> {
+        match self.stack.pop() {
+            Some(Frame::List(list)) => self.value(Item::List(list)),
+            _ => Err(Error::MissingData),
+        }
+    }
+
+    fn begin_map(&mut self, len: Option<usize>) -> Result<(), Error> {
+        if len.is_none() && self.reject_open_streams {
+            return Err(Error::OpenStream);
+        }
+        let capacity = len.unwrap_or(0).min(MAX_PREALLOCATE);
+        self.stack
+            .push(Frame::Map(item::map_with_capacity(capacity), None));
+        Ok(())
+    }
+
+    fn map_key(&mut self, key: String) -> Result<(), Error> {
+        if let Some(Frame::Map(_, pending)) = self.stack.last_mut() {
+            *pending = Some(key);
+        }
+        Ok(())
+    }
+
+    fn end_map(&mut self) -> Result<(), Error> {
+        match self.stack.pop() {
+            Some(Frame::Map(map, _)) => self.value(Item::Map(map)),
+            _ => Err(Error::MissingData),
+        }
+    }
+}
+
+#[test]
+fn counting_visitor_test() {
+    use crate::Parser;
+
+    #[derive(Default)]
+    struct Counter {
+        integers: usize,
+        maps: usize,
+    }
+
+    impl Visitor for Counter {
+        fn on_int16(&mut self, _: i16) -> Result<(), Error> {
+            self.integers += 1;
+            Ok(())
+        }
+
+        fn on_int64(&mut self, _: i64) -> Result<(), Error> {
+            self.integers += 1;
+            Ok(())
+        }
+
+        fn begin_map(&mut self, _: Option<usize>) -> Result<(), Error> {
+            self.maps += 1;
+            Ok(())
+        }
+    }
+
+    // copied from python
+    let data = b"BSDF\x02\x02m\x02\x04testh\x01\x00\x06nestedm\x03\x06nestedy\x04listl\x03h\xff\xffni\x15\xcd[\x07\x00\x00\x00\x00\x04datas\tsome text";
+
+    let mut counter = Counter::default();
+    Parser::new(data.as_slice())
+        .parse_with(&mut counter)
+        .unwrap();
+
+    assert_eq!(counter.integers, 3);
+    assert_eq!(counter.maps, 2);
+}
+
+#[test]
+fn visitor_error_aborts_test() {
+    use crate::Parser;
+
+    struct NoStrings;
+
+    impl Visitor for NoStrings {
+        fn on_string(&mut self, _: String) -> Result<(), Error> {
+            Err(Error::type_mismatch("int16", &Item::String(String::new())))
+        }
+    }
+
+    let data = b"BSDF\x02\x02l\x02h\x01\x00s\x02hi";
+
+    assert_eq!(
+        Parser::new(data.as_slice())
+            .parse_with(&mut NoStrings)
+            .map_err(Error::into_inner),
+        Err(Error::TypeMismatch {
+            expected: "int16",
+            found: "string"
+        })
+    );
+}